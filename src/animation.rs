@@ -9,14 +9,17 @@ use sdl2::pixels::Color;
 use crate::image::Image;
 use crate::text::Text;
 use crate::soundfx::VOL_STEPS;
+use crate::bonus_bomb::BOMB_TYPES;
 
 pub enum AnimationSeq {
     ShipExplosion(i32, i32),
     SpiderExplosion(i32, i32),
     BonusBombHit(i32, i32, usize),
+    BombExplosion(i32, i32),
     ScreenStart(u32),
     ExtraLife,
     VolumeChange(i32),
+    OptionChange(&'static str, &'static str),
 }
 
 pub struct Animation {
@@ -30,9 +33,11 @@ impl Animation {
             AnimationSeq::ShipExplosion(_,_) => 32,
             AnimationSeq::SpiderExplosion(_,_) => 20,
             AnimationSeq::BonusBombHit(_,_,_) => 100,
+            AnimationSeq::BombExplosion(_,_) => 12,
             AnimationSeq::ScreenStart(_) => 100,
             AnimationSeq::ExtraLife => 50,
             AnimationSeq::VolumeChange(_) => 100,
+            AnimationSeq::OptionChange(_,_) => 100,
         };
         Animation {
             animation_seq,
@@ -49,7 +54,7 @@ pub struct Animations<'a, 'b> {
     animation: Vec<Animation>,
     ship_explosion_image: [Image<'a>; 4],
     spider_explosion_image: [Image<'a>; 4],
-    bonus_bomb_image: [Image<'a>; 3],
+    bonus_bomb_image: [Image<'a>; BOMB_TYPES],
     text40: Text<'a, 'b>,
 }
 
@@ -74,6 +79,11 @@ impl<'a, 'b> Animations<'a, 'b> {
                 Image::new(texture_creator, "bonus10_1.png", 50, 22),
                 Image::new(texture_creator, "bonus30_1.png", 50, 22),
                 Image::new(texture_creator, "bonus50_1.png", 50, 22),
+                Image::new(texture_creator, "powerup_double_1.png", 50, 22),
+                Image::new(texture_creator, "powerup_rapid_1.png", 50, 22),
+                Image::new(texture_creator, "powerup_pierce_1.png", 50, 22),
+                Image::new(texture_creator, "powerup_shield_1.png", 50, 22),
+                Image::new(texture_creator, "powerup_slow_1.png", 50, 22),
            ],
            text40: Text::new(ttf_context, 40),
         }
@@ -93,6 +103,10 @@ impl<'a, 'b> Animations<'a, 'b> {
                 else
                     {true});
         }
+        // likewise for option changes
+        if let AnimationSeq::OptionChange(_,_) = animation.animation_seq {
+            self.animation.retain(|a| ! matches!(a.animation_seq, AnimationSeq::OptionChange(..)));
+        }
         self.animation.push(animation);
     }
 
@@ -108,6 +122,9 @@ impl<'a, 'b> Animations<'a, 'b> {
                 AnimationSeq::BonusBombHit(x, y, bomb_type) => {
                     self.render_bomb_hit(canvas, x, y, bomb_type, a.frames_left);
                 }
+                AnimationSeq::BombExplosion(x, y) => {
+                    self.render_bomb_explosion(canvas, x, y, a.frames_left);
+                }
                 AnimationSeq::ScreenStart(num) => {
                     self.render_screen_start(canvas, num, a.frames_left);
                 }
@@ -117,6 +134,9 @@ impl<'a, 'b> Animations<'a, 'b> {
                 AnimationSeq::VolumeChange(new_vol) => {
                     self.render_volume(canvas, new_vol, a.frames_left);
                 }
+                AnimationSeq::OptionChange(name, value) => {
+                    self.render_option(canvas, name, value, a.frames_left);
+                }
             }
         }
     }
@@ -136,6 +156,12 @@ impl<'a, 'b> Animations<'a, 'b> {
         self.spider_explosion_image[((20 - frames_left) / 5) as usize].render(canvas, x, y);
     }
 
+    fn render_bomb_explosion(&self, canvas: &mut Canvas<Window>, x: i32, y: i32, frames_left: u32) {
+        // a shrunken spider explosion centred on the bomb
+        let image = &self.spider_explosion_image[((12 - frames_left) / 3) as usize];
+        image.render_resize(canvas, x - 6, y - 8, 0.4);
+    }
+
     fn render_bomb_hit(&self, canvas: &mut Canvas<Window>, x: i32, y: i32, bomb_type: usize, frames_left: u32) {
         let scale = frames_left as f64 / 100.0;
         let angle = scale * 1800.0;
@@ -167,4 +193,10 @@ impl<'a, 'b> Animations<'a, 'b> {
         }
         canvas.set_draw_color(Color::RGB(0, 0, 0));
     }
+
+    fn render_option(&self, canvas: &mut Canvas<Window>, name: &str, value: &str, frames_left: u32) {
+        self.text40.render(canvas, 120, 250, 79, 120, 181,
+           (frames_left * 255 / 100) as u8,
+           &format!("{}: {}", name, value));
+    }
 }
@@ -0,0 +1,74 @@
+// the attacks that make up a game, the last one repeats for as long as the
+// player lasts
+
+use crate::bonus_bomb::BOMB_TYPES;
+use crate::base_bricks::{Layout, Resupply, THREE_PILES};
+use crate::targeting::{Targeting, Random, Focus, Spread, Nearest};
+
+// every this many attacks the mother turns on the player
+const BOSS_EVERY: u32 = 4;
+
+pub struct Attack {
+    // how often each kind of bonus bomb drops, in the order 10, 30 and 50
+    // letter bricks, double missiles, rapid fire, piercing, shield, slow down
+    pub bonus_weights: [u32; BOMB_TYPES],
+    // how many of the medium spiders are divers, how many of the fast ones
+    // are armoured (up to five) and how many slow ones are thieves (up to
+    // three)
+    pub divers: usize,
+    pub armoured: usize,
+    pub thieves: usize,
+    // the formations, from paths::FORMATIONS, that the spiders leave the
+    // nest in before the rest follow one at a time
+    pub formations: &'static [usize],
+    // how spiders choose the gap in the word to fill and the brick in the
+    // base to take, see targeting.rs
+    pub letter_targeting: &'static dyn Targeting,
+    pub base_targeting: &'static dyn Targeting,
+    // the piles of bricks at the bottom of the screen and what happens once
+    // they are empty
+    pub base_layout: Layout,
+    pub resupply: Resupply,
+    // how many bombs may be in flight at once and whether the spiders only
+    // drop plain bombs straight down rather than each type's own, see
+    // spiders::BOMBING
+    pub bombs: usize,
+    pub plain_bombs: bool,
+}
+
+const FOUR_PILES: Layout = Layout {piles: 4, cols: 3, rows: 4, spacing: 150, x: 50, y: 630};
+const TWO_PILES: Layout = Layout {piles: 2, cols: 6, rows: 5, spacing: 330, x: 60, y: 620};
+
+const ATTACKS: [Attack; 4] = [
+    Attack {bonus_weights: [4, 3, 2, 0, 0, 0, 1, 0], divers: 0, armoured: 0, thieves: 0, formations: &[],
+            letter_targeting: &Random, base_targeting: &Random,
+            base_layout: THREE_PILES, resupply: Resupply::Instant,
+            bombs: 4, plain_bombs: true},
+    Attack {bonus_weights: [3, 3, 2, 1, 1, 0, 1, 1], divers: 2, armoured: 0, thieves: 0, formations: &[1],
+            letter_targeting: &Random, base_targeting: &Nearest,
+            base_layout: FOUR_PILES, resupply: Resupply::Trickle(45),
+            bombs: 6, plain_bombs: false},
+    Attack {bonus_weights: [3, 3, 2, 1, 1, 1, 1, 1], divers: 4, armoured: 2, thieves: 1, formations: &[0, 2],
+            letter_targeting: &Spread, base_targeting: &Nearest,
+            base_layout: TWO_PILES, resupply: Resupply::Delivery(600),
+            bombs: 8, plain_bombs: false},
+    Attack {bonus_weights: [2, 2, 2, 2, 2, 2, 2, 2], divers: 6, armoured: 4, thieves: 2, formations: &[0, 1, 2, 0],
+            letter_targeting: &Focus, base_targeting: &Nearest,
+            base_layout: THREE_PILES, resupply: Resupply::Finite,
+            bombs: 10, plain_bombs: false},
+];
+
+pub fn attack(screen: u32) -> &'static Attack {
+    let i = (screen.max(1) - 1) as usize;
+    &ATTACKS[i.min(ATTACKS.len() - 1)]
+}
+
+// how many attacks past the last one this is, in survival these keep
+// getting harder
+pub fn escalation(screen: u32) -> u32 {
+    screen.saturating_sub(ATTACKS.len() as u32)
+}
+
+pub fn boss_attack(screen: u32) -> bool {
+    screen > 0 && screen.is_multiple_of(BOSS_EVERY)
+}
@@ -1,97 +1,224 @@
-use rand;
-use rand::Rng;
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
 
-use crate::common::TargetBrick;
+use crate::common::{ScreenObjectArea, TargetBrick, SCREEN_HEIGHT, UPDATE_FPS};
 use crate::image::Image;
+use crate::text::Text;
+use crate::spectator;
+use crate::collision::Hitbox;
+use crate::targeting::{Candidate, Targeting, Random};
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay;
 
-const BASE_BRICKS_Y: i32 = 630;
-const BRICKS_HOME_X: i32 = 60;
+// a new set of piles slides in from here
+const BRICKS_START_X: i32 = -600;
 const BRICKS_SPEED: i32 = 2;
 const BRICK_HEIGHT: u32 = 10;
 const BRICK_WIDTH: u32 = 15;
-const BRICK_QTY: usize = 4 * 4 * 3;
+const SUPPLY_X: i32 = 250;
+const SUPPLY_Y: i32 = SCREEN_HEIGHT as i32 - 28;
+// with brick cover on the piles are raised so that the bottom row sits just
+// over the ships, where it can stop the bombs falling on them
+const COVER_BOTTOM_Y: i32 = 555;
 
-pub struct BaseBricks<'a> {
+// how the piles are laid out, x and y being the top left of the first pile
+// once it has slid into place
+#[derive(Copy, Clone, PartialEq)]
+pub struct Layout {
+    pub piles: usize,
+    pub cols: usize,
+    pub rows: usize,
+    pub spacing: i32,
+    pub x: i32,
+    pub y: i32,
+}
+
+// what happens once spiders start emptying the piles
+#[derive(Copy, Clone, PartialEq)]
+pub enum Resupply {
+    // a full set slides in as soon as the last brick is taken
+    Instant,
+    // a brick is put back every so many frames
+    Trickle(u32),
+    // there is no more, spiders wander about once the piles are empty
+    Finite,
+    // a full set slides in this many frames after the last brick is taken
+    Delivery(u32),
+}
+
+pub const THREE_PILES: Layout = Layout {piles: 3, cols: 4, rows: 4, spacing: 210, x: 60, y: 630};
+
+// only the state of the piles, the game keeps the image they are drawn
+// with so that they can be tried out without a display
+pub struct BaseBricks {
     x: i32,
-    filled: [bool; 4 * 4 * 3],
-    targetted: [bool; 4 * 4 * 3],
+    layout: Layout,
+    resupply: Resupply,
+    // frames since the last brick trickled back or the piles were emptied
+    resupply_frames: u32,
+    filled: Vec<bool>,
+    targetted: Vec<bool>,
+    // bricks blown away by bombs, which only come back with a new set
+    lost: Vec<bool>,
     qty_filled: u32,
-    brick_image: Image<'a>,
+    qty_lost: u32,
+    cover: bool,
+    // how spiders choose which brick to take
+    targeting: &'static dyn Targeting,
+}
+
+pub fn brick_image(texture_creator: &TextureCreator<WindowContext>) -> Image<'_> {
+    Image::new(texture_creator, "brick.png", BRICK_WIDTH - 2, BRICK_HEIGHT - 2)
 }
 
-impl<'a> BaseBricks<'a> {
-    pub fn new(texture_creator: &TextureCreator<WindowContext>) -> BaseBricks {
-        BaseBricks {x: 60, filled: [false; BRICK_QTY], targetted: [false; BRICK_QTY],
-            qty_filled: 0, brick_image: Image::new(texture_creator, "brick.png", BRICK_WIDTH - 2, BRICK_HEIGHT - 2)}
+impl BaseBricks {
+    pub fn new() -> BaseBricks {
+        let qty = THREE_PILES.piles * THREE_PILES.cols * THREE_PILES.rows;
+        BaseBricks {x: THREE_PILES.x, layout: THREE_PILES, resupply: Resupply::Instant, resupply_frames: 0,
+            filled: vec![false; qty], targetted: vec![false; qty], lost: vec![false; qty],
+            qty_filled: 0, qty_lost: 0, cover: false,
+            targeting: &Random}
     }
 
     pub fn reset(&mut self) {
-        self.qty_filled = 0;
+        self.refill();
     }
 
-    fn brick_id(pile: usize, col: usize, row: usize) -> usize {
-        pile * 16 + row * 4 + col
+    // a new layout starts with a full set sliding in, the same one carries
+    // on from where it was
+    pub fn set_layout(&mut self, layout: Layout, resupply: Resupply) {
+        self.resupply = resupply;
+        if layout != self.layout {
+            self.layout = layout;
+            self.refill();
+        }
+    }
+
+    fn refill(&mut self) {
+        let qty = self.brick_qty();
+        self.x = BRICKS_START_X;
+        self.filled = vec![true; qty];
+        self.targetted = vec![false; qty];
+        self.lost = vec![false; qty];
+        self.qty_filled = qty as u32;
+        self.qty_lost = 0;
+        self.resupply_frames = 0;
+    }
+
+    fn brick_qty(&self) -> usize {
+        self.layout.piles * self.pile_qty()
+    }
+
+    fn pile_qty(&self) -> usize {
+        self.layout.cols * self.layout.rows
+    }
+
+    fn brick_id(&self, pile: usize, col: usize, row: usize) -> usize {
+        pile * self.pile_qty() + row * self.layout.cols + col
+    }
+
+    pub fn set_targeting(&mut self, targeting: &'static dyn Targeting) {
+        self.targeting = targeting;
+    }
+
+    pub fn set_cover(&mut self, cover: bool) {
+        self.cover = cover;
+    }
+
+    // the top of the piles
+    fn top_y(&self) -> i32 {
+        if self.cover {COVER_BOTTOM_Y - (self.layout.rows as u32 * BRICK_HEIGHT) as i32} else {self.layout.y}
+    }
+
+    fn brick_position(&self, id: usize) -> (i32, i32) {
+        let pile = id / self.pile_qty();
+        let col = id % self.layout.cols;
+        let row = (id % self.pile_qty()) / self.layout.cols;
+        (self.x + pile as i32 * self.layout.spacing + col as i32 * BRICK_WIDTH as i32,
+         self.top_y() + row as i32 * BRICK_HEIGHT as i32)
+    }
+
+    // how much of the pile has been taken away
+    fn pile_progress(&self, pile: usize) -> f64 {
+        let from = pile * self.pile_qty();
+        let taken = self.filled[from..from + self.pile_qty()].iter().filter(|&&f| ! f).count();
+        taken as f64 / self.pile_qty() as f64
     }
 
     fn row_has_bricks(&self, pile: usize, row: usize) -> bool {
-        let i = pile * 16 + row * 4;
-        self.filled[i] || self.filled[i + 1] || self.filled[i + 2] || self.filled[i + 3]
+        (0..self.layout.cols).any(|col| self.filled[self.brick_id(pile, col, row)])
     }
 
     pub fn update(&mut self) {
-        if self.qty_filled == 0 {
-            self.x = -600;
-            self.filled = [true; BRICK_QTY];
-            self.targetted = [false; BRICK_QTY];
-            self.qty_filled = BRICK_QTY as u32;
+        match self.resupply {
+            Resupply::Instant => {
+                if self.qty_filled == 0 {
+                    self.refill();
+                }
+            },
+            Resupply::Trickle(frames) => {
+                if self.qty_filled < self.brick_qty() as u32 {
+                    self.resupply_frames += 1;
+                    if self.resupply_frames >= frames {
+                        self.resupply_frames = 0;
+                        self.return_brick();
+                    }
+                }
+            },
+            Resupply::Finite => {},
+            Resupply::Delivery(frames) => {
+                if self.qty_filled == 0 {
+                    self.resupply_frames += 1;
+                    if self.resupply_frames >= frames {
+                        self.refill();
+                    }
+                }
+            },
         }
-        else if self.x < BRICKS_HOME_X {
-             self.x += BRICKS_SPEED;
+        if self.x < self.layout.x {
+             self.x = (self.x + BRICKS_SPEED).min(self.layout.x);
         }
     }
 
-    pub fn request_target(&mut self) -> Option<TargetBrick> {
+    // x, y is the top left of the spider asking
+    pub fn request_target(&mut self, x: f64, y: f64) -> Option<TargetBrick> {
         // build up a list of all bricks that are in the top row of their pile that
         // are not already targetted and are not next to another that is targetted
-        let mut target_list = [0; 12];
-        let mut list_len: usize = 0;
-        if self.qty_filled > 0 && self.x >= BRICKS_HOME_X {
-            for i in 0..3 {
-                for j in 0..4 {
+        let mut target_list: Vec<usize> = Vec::new();
+        if self.qty_filled > 0 && self.x >= self.layout.x {
+            for i in 0..self.layout.piles {
+                for j in 0..self.layout.rows {
                     if self.row_has_bricks(i, j) {
-                        let id0 = BaseBricks::brick_id(i, 0, j);
-                        let t0 = self.targetted[id0];
-                        let t1 = self.targetted[id0 + 1];
-                        let t2 = self.targetted[id0 + 2];
-                        let t3 = self.targetted[id0 + 3];
-                        if (! t0) && (! t1) && self.filled[id0] {
-                            target_list[list_len] = id0;
-                            list_len += 1;
-                        }
-                        if (! t0) && (! t1) && (! t2) && self.filled[id0 + 1] {
-                            target_list[list_len] = id0 + 1;
-                            list_len += 1;
-                        }
-                        if (! t1) && (! t2) && (! t3) && self.filled[id0 + 2] {
-                            target_list[list_len] = id0 + 2;
-                            list_len += 1;
-                        }
-                        if (! t2) && (! t3) && self.filled[id0 + 3] {
-                            target_list[list_len] = id0 + 3;
-                            list_len += 1;
+                        let id0 = self.brick_id(i, 0, j);
+                        let cols = self.layout.cols;
+                        for k in 0..cols {
+                            let id = id0 + k;
+                            let left = k > 0 && self.targetted[id - 1];
+                            let right = k + 1 < cols && self.targetted[id + 1];
+                            if (! left) && (! self.targetted[id]) && (! right) && self.filled[id] {
+                                target_list.push(id);
+                            }
                         }
                         break;
                     }
                 }
             }
         }
-        // choose a random target from the list
-        if list_len > 0 {
-            let id = target_list[rand::thread_rng().gen_range(0..list_len)];
-            let x = self.x + (id as i32 / 16) * 210 + (id as i32 % 4) * BRICK_WIDTH as i32;
-            let y = BASE_BRICKS_Y + ((id as i32 % 16) / 4) * BRICK_HEIGHT as i32;
+        // and let the strategy for the attack choose from the list
+        if ! target_list.is_empty() {
+            let pile_qty = self.pile_qty();
+            let candidates: Vec<Candidate> = target_list.iter()
+                .map(|&id| {
+                    let (brick_x, brick_y) = self.brick_position(id);
+                    Candidate {id, x: brick_x, y: brick_y, group: id / pile_qty,
+                               progress: self.pile_progress(id / pile_qty)}
+                })
+                .collect();
+            let id = candidates[self.targeting.choose(&candidates, x, y)].id;
+            let (x, y) = self.brick_position(id);
             self.targetted[id] = true;
             Some(TargetBrick {x, y, brick_id: id})
         }
@@ -100,27 +227,104 @@ impl<'a> BaseBricks<'a> {
         }
     }
 
-    pub fn take_target(&mut self, brick_id: usize) {
-        self.filled[brick_id] = false;
+    // false if a bomb got to the brick first
+    pub fn take_target(&mut self, brick_id: usize) -> bool {
+        let taken = self.filled[brick_id];
+        if taken {
+            self.filled[brick_id] = false;
+            self.qty_filled -= 1;
+        }
         self.targetted[brick_id] = false;
-        self.qty_filled -= 1;
+        taken
+    }
+
+    fn brick_area(&self, id: usize) -> ScreenObjectArea {
+        let (x, y) = self.brick_position(id);
+        ScreenObjectArea::new(x, y, BRICK_WIDTH - 2, BRICK_HEIGHT - 2)
+    }
+
+    // a bomb destroys the first brick it hits, returns where that was
+    pub fn bomb_collision(&mut self, hitbox: &Hitbox) -> Option<(i32, i32)> {
+        let hit = (0..self.brick_qty())
+            .filter(|&id| self.filled[id])
+            .find(|&id| hitbox.collides(&Hitbox::new(self.brick_area(id))));
+        hit.map(|id| {
+            self.filled[id] = false;
+            self.lost[id] = true;
+            self.qty_filled -= 1;
+            self.qty_lost += 1;
+            self.brick_position(id)
+        })
+    }
+
+    // a brick dropped by a spider goes back into the lowest gap in the piles
+    // that a spider took a brick from
+    pub fn return_brick(&mut self) {
+        let layout = self.layout;
+        let gap = (0..layout.rows).rev()
+            .flat_map(|row| (0..layout.piles).flat_map(move |pile| (0..layout.cols).map(move |col| (pile, col, row))))
+            .map(|(pile, col, row)| self.brick_id(pile, col, row))
+            .find(|&id| ! self.filled[id] && ! self.lost[id]);
+        if let Some(id) = gap {
+            self.filled[id] = true;
+            self.qty_filled += 1;
+        }
     }
 
     pub fn untarget(&mut self, brick_id: usize) {
         self.targetted[brick_id] = false;
     }
 
-    pub fn render(&self, canvas: &mut Canvas<Window>) {
-        for i in 0..3 {
-            for j in 0..4 {
-                for k in 0..4 {
-                    if self.filled[BaseBricks::brick_id(i, j, k)] {
-                        let y = BASE_BRICKS_Y + (k as i32) * BRICK_HEIGHT as i32;
-                        let x = self.x + i as i32 * 210 + j as i32 * BRICK_WIDTH as i32;
-                        self.brick_image.render(canvas, x, y);
-                    }
-                }
-            }
+    pub fn untarget_all(&mut self) {
+        self.targetted.fill(false);
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        (self.x, &self.filled, &self.targetted, &self.lost, self.qty_filled, self.resupply_frames).hash(state);
+    }
+
+    // x:filled:frames towards the next resupply:lost:cover, the layout is
+    // the one for the attack
+    pub fn describe(&self) -> String {
+        format!("{}:{}:{}:{}:{}", self.x, spectator::bits_to_hex(&self.filled), self.resupply_frames,
+            spectator::bits_to_hex(&self.lost), self.cover)
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        self.x = spectator::field(&fields, 0, self.x);
+        spectator::hex_to_bits(fields.get(1).unwrap_or(&""), &mut self.filled);
+        self.qty_filled = self.filled.iter().filter(|&&f| f).count() as u32;
+        self.resupply_frames = spectator::field(&fields, 2, 0);
+        spectator::hex_to_bits(fields.get(3).unwrap_or(&""), &mut self.lost);
+        self.qty_lost = self.lost.iter().filter(|&&l| l).count() as u32;
+        self.cover = spectator::field(&fields, 4, false);
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>, brick_image: &Image) {
+        for id in (0..self.brick_qty()).filter(|&id| self.filled[id]) {
+            let (x, y) = self.brick_position(id);
+            brick_image.render(canvas, x, y);
+        }
+    }
+
+    // what is left in the piles, or how long until the next delivery
+    pub fn render_supply(&self, canvas: &mut Canvas<Window>, text: &Text) {
+        let supply = match self.resupply {
+            Resupply::Finite => {format!("Supply {}", self.qty_filled)},
+            Resupply::Delivery(frames) if self.qty_filled == 0 => {
+                format!("Delivery {}s", frames.saturating_sub(self.resupply_frames).div_ceil(UPDATE_FPS))
+            },
+            _ => {format!("Bricks {}", self.qty_filled)},
+        };
+        let supply = if self.qty_lost > 0 {format!("{}, {} lost", supply, self.qty_lost)} else {supply};
+        text.render(canvas, SUPPLY_X, SUPPLY_Y, 79, 120, 181, 255, &supply);
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        for id in (0..self.brick_qty()).filter(|&id| self.targetted[id]) {
+            debug_overlay::render_area(canvas, &self.brick_area(id), Color::RGB(255, 255, 0));
         }
     }
 }
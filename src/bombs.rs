@@ -1,43 +1,170 @@
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
 
-use crate::common::{ScreenObjectArea, SCREEN_HEIGHT};
+use crate::common::{ScreenObjectArea, SCREEN_WIDTH, SCREEN_HEIGHT};
 use crate::image::Image;
+use crate::spectator;
+use crate::collision::{Hitbox, CollisionMode};
+use crate::soundfx::SoundEffect;
+use crate::base_bricks::BaseBricks;
+use crate::animation::{AnimationSeq, Animation, Animations};
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay;
 
 const BOMB_WIDTH: u32 = 3;
 const BOMB_HEIGHT: u32 = 15;
-const BOMB_SPEED: i32 = 4;
-const MAX_BOMBS: usize = 4;
+const BOMB_SPEED: f64 = 4.0;
+// as many as there have always been, until an attack says otherwise
+const BOMBS_IN_FLIGHT: usize = 4;
+// escalating attacks in survival allow more in flight than the attack
+// does, up to this many
+const EXTRA_BOMBS: usize = 2;
+const MAX_BOMBS: usize = 24;
+// aim for the middle of the ships
+const SHIP_Y: f64 = 585.0;
+// aimed bombs are kept this close to straight down, in radians
+const MAX_AIM_ANGLE: f64 = 0.6;
+const SPREAD_ANGLE: f64 = 0.35;
+// homing bombs are slow and only steer until they are low enough that
+// there is still time to get out of the way
+const HOMING_SPEED: f64 = 2.0;
+const HOMING_TURN: f64 = 0.05;
+const HOMING_MAX_X_VEL: f64 = 1.5;
+const HOMING_MAX_Y: f64 = 420.0;
+// split bombs break into three at this height
+const SPLIT_Y: f64 = 340.0;
+const SPLIT_ANGLE: f64 = 0.45;
+
+// how a spider lets its bombs go
+#[derive(Copy, Clone)]
+pub enum Volley {
+    Straight,
+    // at where the nearest ship is now
+    Aimed,
+    // at where the nearest ship will be if it keeps going
+    Leading,
+    // three bombs fanned out around straight down
+    Spread,
+    Homing,
+    Split,
+}
+
+#[derive(Copy, Clone, PartialEq, Hash)]
+enum Kind {
+    Plain,
+    Homing,
+    Split,
+}
+
+impl Kind {
+    fn code(&self) -> &'static str {
+        match *self {
+            Kind::Plain => {"P"},
+            Kind::Homing => {"H"},
+            Kind::Split => {"S"},
+        }
+    }
+}
 
 #[derive(Copy, Clone)]
 struct Bomb {
-    x: i32,
-    y: i32,
+    x: f64,
+    y: f64,
+    prev_x: f64,
+    prev_y: f64,
+    x_vel: f64,
+    y_vel: f64,
+    kind: Kind,
     in_flight: bool,
 }
 
 impl Bomb {
     fn area(&self) -> ScreenObjectArea {
-        ScreenObjectArea::new(self.x, self.y, BOMB_WIDTH, BOMB_HEIGHT)
+        ScreenObjectArea::new(self.x as i32, self.y as i32, BOMB_WIDTH, BOMB_HEIGHT)
+            .moving(self.x as i32 - self.prev_x as i32, self.y as i32 - self.prev_y as i32)
     }
 
-    fn update(&mut self) {
+    // bombs are drawn turned the way they are falling
+    fn hitbox(&self, collision_mode: CollisionMode) -> Hitbox<'static> {
+        Hitbox::new(self.area()).rotated(self.angle()).mode(collision_mode)
+    }
+
+    fn centre_x(&self) -> f64 {
+        self.x + BOMB_WIDTH as f64 / 2.0
+    }
+
+    // degrees clockwise from pointing straight down
+    fn angle(&self) -> f64 {
+        (- self.x_vel).atan2(self.y_vel).to_degrees()
+    }
+
+    fn update(&mut self, ship_xs: &[i32]) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
         if self.in_flight {
-            self.y += BOMB_SPEED;
-            self.in_flight = self.y < SCREEN_HEIGHT as i32;
+            if self.kind == Kind::Homing && self.y < HOMING_MAX_Y {
+                if let Some(target_x) = nearest(ship_xs, self.centre_x()) {
+                    let turn = (target_x - self.centre_x()).clamp(-HOMING_TURN, HOMING_TURN);
+                    self.x_vel = (self.x_vel + turn).clamp(-HOMING_MAX_X_VEL, HOMING_MAX_X_VEL);
+                }
+            }
+            self.x += self.x_vel;
+            self.y += self.y_vel;
+            self.in_flight = self.y < SCREEN_HEIGHT as f64 &&
+                self.x > - (BOMB_HEIGHT as f64) && self.x < SCREEN_WIDTH as f64;
         }
     }
 }
 
+// the x of the ship closest to x
+fn nearest(ship_xs: &[i32], x: f64) -> Option<f64> {
+    ship_xs.iter()
+        .map(|&ship_x| ship_x as f64)
+        .min_by(|a, b| (a - x).abs().total_cmp(&(b - x).abs()))
+}
+
+// the velocity of a bomb heading for the target, but never flatter than
+// the most a bomb can be aimed
+fn aim(x: f64, y: f64, target_x: f64) -> (f64, f64) {
+    let angle = (target_x - x).atan2((SHIP_Y - y).max(1.0)).clamp(-MAX_AIM_ANGLE, MAX_AIM_ANGLE);
+    velocity(angle, BOMB_SPEED)
+}
+
+// angle is anticlockwise from straight down
+fn velocity(angle: f64, speed: f64) -> (f64, f64) {
+    (angle.sin() * speed, angle.cos() * speed)
+}
+
 pub struct Bombs<'a> {
     bomb: [Bomb; MAX_BOMBS],
+    // how many of them may be in flight at once
+    limit: usize,
     bomb_image: Image<'a>,
+    homing_image: Image<'a>,
+    split_image: Image<'a>,
+    bomb_explode_sound: SoundEffect,
+    // bombs collide the same way as the ships they are dropped on
+    collision_mode: CollisionMode,
+    // where the ships are this frame and were last frame, to aim at
+    ship_xs: Vec<i32>,
+    prev_ship_xs: Vec<i32>,
 }
 
 impl<'a> Bombs<'a> {
-    pub fn new(texture_creator: &TextureCreator<WindowContext>) -> Bombs {
-        Bombs {bomb: [Bomb {x: 0, y: 0, in_flight: false}; MAX_BOMBS],
-               bomb_image: Image::new(texture_creator, "bomb.png", BOMB_WIDTH, BOMB_HEIGHT)}
+    pub fn new(texture_creator: &TextureCreator<WindowContext>, collision_mode: CollisionMode) -> Bombs<'_> {
+        Bombs {bomb: [Bomb {x: 0.0, y: 0.0, prev_x: 0.0, prev_y: 0.0, x_vel: 0.0, y_vel: 0.0,
+                            kind: Kind::Plain, in_flight: false}; MAX_BOMBS],
+               limit: BOMBS_IN_FLIGHT,
+               bomb_image: Image::new(texture_creator, "bomb.png", BOMB_WIDTH, BOMB_HEIGHT),
+               homing_image: Image::new(texture_creator, "bomb_homing.png", BOMB_WIDTH, BOMB_HEIGHT),
+               split_image: Image::new(texture_creator, "bomb_split.png", BOMB_WIDTH, BOMB_HEIGHT),
+               bomb_explode_sound: SoundEffect::new("bomb_explosion.wav"),
+               collision_mode,
+               ship_xs: Vec::with_capacity(2),
+               prev_ship_xs: Vec::with_capacity(2)}
     }
 
     pub fn reset(&mut self) {
@@ -46,19 +173,95 @@ impl<'a> Bombs<'a> {
         }
     }
 
+    pub fn set_collision_mode(&mut self, collision_mode: CollisionMode) {
+        self.collision_mode = collision_mode;
+    }
+
+    // how many bombs the attack lets fly at once, each attack past the last
+    // in survival lets more fly, see attacks::escalation
+    pub fn set_limit(&mut self, bombs: usize, escalation: u32) {
+        self.limit = (bombs + escalation as usize * EXTRA_BOMBS).min(MAX_BOMBS);
+    }
+
+    // a plain bomb straight down
     pub fn release(&mut self, x: i32, y: i32) -> bool {
-        for b in self.bomb.iter_mut().filter(|b| ! b.in_flight).take(1) {
-            b.x = x - BOMB_WIDTH as i32 / 2;
-            b.y = y;
-            b.in_flight = true;
-            return true;
+        self.launch(x as f64, y as f64, (0.0, BOMB_SPEED), Kind::Plain)
+    }
+
+    // x, y is the middle of the top of the bomb, true if any bomb was let go
+    pub fn release_volley(&mut self, x: i32, y: i32, volley: Volley) -> bool {
+        let (x, y) = (x as f64, y as f64);
+        match volley {
+            Volley::Straight => {self.launch(x, y, (0.0, BOMB_SPEED), Kind::Plain)},
+            Volley::Aimed => {
+                let vel = match nearest(&self.ship_xs, x) {
+                    Some(target_x) => {aim(x, y, target_x)},
+                    None => {(0.0, BOMB_SPEED)},
+                };
+                self.launch(x, y, vel, Kind::Plain)
+            },
+            Volley::Leading => {
+                let vel = match self.ship_xs.iter().zip(&self.prev_ship_xs)
+                    .map(|(&ship_x, &prev_x)| (ship_x as f64, (ship_x - prev_x) as f64))
+                    .min_by(|a, b| (a.0 - x).abs().total_cmp(&(b.0 - x).abs())) {
+                    Some((target_x, ship_vel)) => {
+                        // near enough the time the bomb takes to get there
+                        let frames = (SHIP_Y - y).max(0.0) / BOMB_SPEED;
+                        let target_x = (target_x + ship_vel * frames).clamp(0.0, SCREEN_WIDTH as f64);
+                        aim(x, y, target_x)
+                    },
+                    None => {(0.0, BOMB_SPEED)},
+                };
+                self.launch(x, y, vel, Kind::Plain)
+            },
+            Volley::Spread => {
+                let mut released = false;
+                for angle in [0.0, -SPREAD_ANGLE, SPREAD_ANGLE] {
+                    released |= self.launch(x, y, velocity(angle, BOMB_SPEED), Kind::Plain);
+                }
+                released
+            },
+            Volley::Homing => {self.launch(x, y, (0.0, HOMING_SPEED), Kind::Homing)},
+            Volley::Split => {self.launch(x, y, (0.0, BOMB_SPEED), Kind::Split)},
+        }
+    }
+
+    fn launch(&mut self, x: f64, y: f64, vel: (f64, f64), kind: Kind) -> bool {
+        if self.bomb.iter().filter(|b| b.in_flight).count() >= self.limit {
+            return false;
+        }
+        match self.bomb.iter_mut().find(|b| ! b.in_flight) {
+            Some(b) => {
+                b.x = x - (BOMB_WIDTH / 2) as f64;
+                b.y = y;
+                b.prev_x = b.x;
+                b.prev_y = y;
+                (b.x_vel, b.y_vel) = vel;
+                b.kind = kind;
+                b.in_flight = true;
+                true
+            },
+            None => {false},
         }
-        return false;
     }
 
-    pub fn collision(&mut self, col_area: ScreenObjectArea) -> bool {
+    // split bombs that have fallen far enough carry on straight down as a
+    // plain bomb with one more either side, if there is room for them
+    fn split(&mut self) {
+        for i in 0..MAX_BOMBS {
+            let b = self.bomb[i];
+            if b.in_flight && b.kind == Kind::Split && b.y >= SPLIT_Y {
+                self.bomb[i].kind = Kind::Plain;
+                for angle in [-SPLIT_ANGLE, SPLIT_ANGLE] {
+                    self.launch(b.centre_x(), b.y, velocity(angle, BOMB_SPEED), Kind::Plain);
+                }
+            }
+        }
+    }
+
+    pub fn collision(&mut self, hitbox: &Hitbox) -> bool {
         for b in self.bomb.iter_mut()
-            .filter(|b| b.in_flight && col_area.collides(b.area()))
+            .filter(|b| b.in_flight && hitbox.collides(&b.hitbox(self.collision_mode)))
             .take(1) {
             // once bomb has collided, it is no more, take care of it here
             b.in_flight = false;
@@ -67,20 +270,140 @@ impl<'a> Bombs<'a> {
         return false;
     }
 
+    pub fn shoot_down(&mut self, hitbox: &Hitbox, animations: &mut Animations) -> bool {
+        match self.bomb.iter_mut()
+            .find(|b| b.in_flight && hitbox.collides(&b.hitbox(self.collision_mode))) {
+            Some(b) => {
+                b.in_flight = false;
+                let x = b.x as i32 + BOMB_WIDTH as i32 / 2;
+                let y = b.y as i32 + BOMB_HEIGHT as i32 / 2;
+                let animation = Animation::new(AnimationSeq::BombExplosion(x, y));
+                animations.register(animation);
+                self.bomb_explode_sound.play();
+                true
+            },
+            None => {false},
+        }
+    }
+
+    // with brick cover on, a bomb stops at the first brick it hits and
+    // takes it out
+    pub fn cover_collision(&mut self, base_bricks: &mut BaseBricks, animations: &mut Animations) {
+        for b in self.bomb.iter_mut().filter(|b| b.in_flight) {
+            if let Some((x, y)) = base_bricks.bomb_collision(&b.hitbox(self.collision_mode)) {
+                b.in_flight = false;
+                let animation = Animation::new(AnimationSeq::BombExplosion(x + 7, y + 5));
+                animations.register(animation);
+                self.bomb_explode_sound.play();
+            }
+        }
+    }
+
     pub fn in_flight(&self) -> bool {
         self.bomb.iter().any(|&b| b.in_flight)
     }
 
-    pub fn update(&mut self) {
+    pub fn in_flight_between(&self, x_min: i32, x_max: i32) -> bool {
+        self.bomb.iter().any(|&b| b.in_flight &&
+            b.x as i32 + BOMB_WIDTH as i32 > x_min && (b.x as i32) < x_max)
+    }
+
+    // the centres of the ships, for bombs to aim at
+    pub fn update(&mut self, ship_xs: &[i32]) {
+        self.prev_ship_xs.clear();
+        self.prev_ship_xs.extend_from_slice(&self.ship_xs);
+        self.ship_xs.clear();
+        self.ship_xs.extend_from_slice(ship_xs);
         for b in &mut self.bomb {
-            b.update();
+            b.update(ship_xs);
+        }
+        self.split();
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        for b in self.bomb.iter().filter(|&b| b.in_flight) {
+            (b.x.to_bits(), b.y.to_bits(), b.x_vel.to_bits(), b.y_vel.to_bits(), b.kind).hash(state);
+        }
+    }
+
+    // x:y:kind:x velocity:y velocity for each bomb
+    pub fn describe(&self) -> String {
+        let bombs: Vec<String> = self.bomb.iter().filter(|&b| b.in_flight)
+            .map(|b| format!("{}:{}:{}:{:.2}:{:.2}", b.x as i32, b.y as i32, b.kind.code(), b.x_vel, b.y_vel))
+            .collect();
+        spectator::list(&bombs)
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        self.reset();
+        for (b, d) in self.bomb.iter_mut().zip(spectator::items(description)) {
+            let fields: Vec<&str> = d.split(':').collect();
+            b.x = spectator::field(&fields, 0, 0.0);
+            b.y = spectator::field(&fields, 1, 0.0);
+            b.kind = match fields.get(2) {
+                Some(&"H") => {Kind::Homing},
+                Some(&"S") => {Kind::Split},
+                _ => {Kind::Plain},
+            };
+            b.x_vel = spectator::field(&fields, 3, 0.0);
+            b.y_vel = spectator::field(&fields, 4, BOMB_SPEED);
+            b.in_flight = true;
         }
     }
 
     pub fn render(&self, canvas: &mut Canvas<Window>) {
         for b in self.bomb.iter().filter(|&b| b.in_flight) {
-            self.bomb_image.render(canvas, b.x, b.y);
+            let image = match b.kind {
+                Kind::Plain => {&self.bomb_image},
+                Kind::Homing => {&self.homing_image},
+                Kind::Split => {&self.split_image},
+            };
+            image.render_angle(canvas, b.x as i32, b.y as i32, b.angle(), 1.0);
+        }
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        for b in self.bomb.iter().filter(|&b| b.in_flight) {
+            debug_overlay::render_area(canvas, &b.area(), Color::RGB(255, 0, 255));
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a bomb aimed at a ship under the first pile, true if it gets through
+    fn bomb_gets_through(cover: bool, collision_mode: CollisionMode) -> bool {
+        let mut base_bricks = BaseBricks::new();
+        base_bricks.set_cover(cover);
+        base_bricks.reset();
+        // let the piles slide into place
+        for _ in 0..400 {
+            base_bricks.update();
+        }
+        let ship = Hitbox::new(ScreenObjectArea::new(75, 565, 30, 40));
+        let (x_vel, y_vel) = aim(90.0, 300.0, 90.0);
+        let mut bomb = Bomb { x: 89.0, y: 300.0, prev_x: 89.0, prev_y: 300.0, x_vel, y_vel,
+            kind: Kind::Plain, in_flight: true };
+        while bomb.in_flight {
+            bomb.update(&[90]);
+            if cover && base_bricks.bomb_collision(&bomb.hitbox(collision_mode)).is_some() {
+                return false;
+            }
+            if bomb.hitbox(collision_mode).collides(&ship) {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn brick_cover_stops_a_bomb_aimed_at_the_ship() {
+        for collision_mode in [CollisionMode::Aabb, CollisionMode::RotatedBox] {
+            assert!(bomb_gets_through(false, collision_mode));
+            assert!(! bomb_gets_through(true, collision_mode));
+        }
+    }
+}
@@ -1,27 +1,70 @@
-use rand;
-use rand::Rng;
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
 use crate::soundfx::SoundEffect;
 
 use crate::letter_bricks::LetterBricks;
-use crate::common::{ScreenObjectArea, SCREEN_HEIGHT};
+use crate::power_ups::{PowerUp, PowerUps};
+use crate::common::{self, ScreenObjectArea, SCREEN_HEIGHT};
 use crate::image::Image;
+use crate::spectator;
 use crate::animation::{AnimationSeq, Animation, Animations};
+use crate::collision::{CollisionMode, CollisionMask, Hitbox};
+use crate::effects::ScreenEffects;
 
 const START_Y: i32 = 120;
 const BOMB_WIDTH: u32 = 50;
 const BOMB_HEIGHT: u32 = 22;
 const BOMB_SPEED: i32 = 2;
 const BOMB_PERIOD: u32 = 10;
+// only the middle part of the bonus bomb is collidable
+const BOMB_INSET: u32 = 10;
 const BOMB_VALUE: [usize; 3] = [10, 30, 50];
 const SCORE_MULTIPLIER: u32 = 20;
+const POWER_UP_SCORE: u32 = 500;
+pub const BOMB_TYPES: usize = 8;
+// until an attack says otherwise only the letter bonuses drop
+const DEFAULT_WEIGHTS: [u32; BOMB_TYPES] = [1, 1, 1, 0, 0, 0, 0, 0];
 
+// the first three remove letter bricks, the rest grant a power-up
 #[derive(Copy, Clone)]
 enum BombType {
     Bonus10,
     Bonus30,
     Bonus50,
+    DoubleMissile,
+    RapidFire,
+    Piercing,
+    Shield,
+    SlowDown,
+}
+
+impl BombType {
+    fn from_index(i: usize) -> BombType {
+        match i {
+            0 => {BombType::Bonus10},
+            1 => {BombType::Bonus30},
+            2 => {BombType::Bonus50},
+            3 => {BombType::DoubleMissile},
+            4 => {BombType::RapidFire},
+            5 => {BombType::Piercing},
+            6 => {BombType::Shield},
+            _ => {BombType::SlowDown},
+        }
+    }
+
+    fn power_up(&self) -> Option<PowerUp> {
+        match *self {
+            BombType::DoubleMissile => {Some(PowerUp::DoubleMissile)},
+            BombType::RapidFire => {Some(PowerUp::RapidFire)},
+            BombType::Piercing => {Some(PowerUp::Piercing)},
+            BombType::Shield => {Some(PowerUp::Shield)},
+            BombType::SlowDown => {Some(PowerUp::SlowDown)},
+            _ => {None},
+        }
+    }
 }
 
 enum State {
@@ -32,36 +75,69 @@ enum State {
 pub struct BonusBomb<'a> {
     x: i32,
     y: i32,
+    prev_y: i32,
     bomb_state: State,
     bomb_type: BombType,
-    bomb_image: [[Image<'a>; 2]; 3],
+    weights: [u32; BOMB_TYPES],
+    bomb_image: [[Image<'a>; 2]; BOMB_TYPES],
+    bomb_mask: [CollisionMask; BOMB_TYPES],
+    collision_mode: CollisionMode,
     bonus_bomb_sound: SoundEffect,
     bonus_bomb_hit_sound: SoundEffect,
     sound_on: bool,
 }
 
 impl<'a> BonusBomb<'a> {
-    pub fn new(texture_creator: &TextureCreator<WindowContext>) -> BonusBomb {
+    pub fn new(texture_creator: &TextureCreator<WindowContext>, collision_mode: CollisionMode) -> BonusBomb {
         BonusBomb {
             x: 0,
             y: 0,
+            prev_y: 0,
             bomb_state: State::Dormant,
             bomb_type: BombType::Bonus10,
+            weights: DEFAULT_WEIGHTS,
             bomb_image: [[Image::new(texture_creator, "bonus10_1.png", BOMB_WIDTH, BOMB_HEIGHT),
                           Image::new(texture_creator, "bonus10_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
                          [Image::new(texture_creator, "bonus30_1.png", BOMB_WIDTH, BOMB_HEIGHT),
                           Image::new(texture_creator, "bonus30_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
                          [Image::new(texture_creator, "bonus50_1.png", BOMB_WIDTH, BOMB_HEIGHT),
-                          Image::new(texture_creator, "bonus50_2.png", BOMB_WIDTH, BOMB_HEIGHT)]],
+                          Image::new(texture_creator, "bonus50_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
+                         [Image::new(texture_creator, "powerup_double_1.png", BOMB_WIDTH, BOMB_HEIGHT),
+                          Image::new(texture_creator, "powerup_double_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
+                         [Image::new(texture_creator, "powerup_rapid_1.png", BOMB_WIDTH, BOMB_HEIGHT),
+                          Image::new(texture_creator, "powerup_rapid_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
+                         [Image::new(texture_creator, "powerup_pierce_1.png", BOMB_WIDTH, BOMB_HEIGHT),
+                          Image::new(texture_creator, "powerup_pierce_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
+                         [Image::new(texture_creator, "powerup_shield_1.png", BOMB_WIDTH, BOMB_HEIGHT),
+                          Image::new(texture_creator, "powerup_shield_2.png", BOMB_WIDTH, BOMB_HEIGHT)],
+                         [Image::new(texture_creator, "powerup_slow_1.png", BOMB_WIDTH, BOMB_HEIGHT),
+                          Image::new(texture_creator, "powerup_slow_2.png", BOMB_WIDTH, BOMB_HEIGHT)]],
+            bomb_mask: [CollisionMask::new(&["bonus10_1.png", "bonus10_2.png"]),
+                        CollisionMask::new(&["bonus30_1.png", "bonus30_2.png"]),
+                        CollisionMask::new(&["bonus50_1.png", "bonus50_2.png"]),
+                        CollisionMask::new(&["powerup_double_1.png", "powerup_double_2.png"]),
+                        CollisionMask::new(&["powerup_rapid_1.png", "powerup_rapid_2.png"]),
+                        CollisionMask::new(&["powerup_pierce_1.png", "powerup_pierce_2.png"]),
+                        CollisionMask::new(&["powerup_shield_1.png", "powerup_shield_2.png"]),
+                        CollisionMask::new(&["powerup_slow_1.png", "powerup_slow_2.png"])]
+                .map(|mask| mask.cropped(BOMB_INSET as f64 / BOMB_WIDTH as f64,
+                                         (BOMB_WIDTH - BOMB_INSET) as f64 / BOMB_WIDTH as f64)),
+            collision_mode,
             bonus_bomb_sound: SoundEffect::new("bonus_bomb.ogg"),
             bonus_bomb_hit_sound: SoundEffect::new("spider_explosion.ogg"),   // for now
             sound_on: false,
         } 
     }
 
-    fn area(&self) -> ScreenObjectArea {
-        // only the middle part of the bonus bomb is collidable
-        ScreenObjectArea::new(self.x + 10, self.y, BOMB_WIDTH - 20, BOMB_HEIGHT)
+    // the masks are cropped to the same middle part as the area
+    fn hitbox(&self) -> Hitbox<'_> {
+        Hitbox::new(ScreenObjectArea::new(self.x + BOMB_INSET as i32, self.y, BOMB_WIDTH - 2 * BOMB_INSET, BOMB_HEIGHT)
+            .moving(0, self.y - self.prev_y))
+            .shaped(self.collision_mode, &self.bomb_mask[self.bomb_type as usize])
+    }
+
+    pub fn set_collision_mode(&mut self, collision_mode: CollisionMode) {
+        self.collision_mode = collision_mode;
     }
 
     pub fn in_flight(&self) -> bool {
@@ -75,36 +151,54 @@ impl<'a> BonusBomb<'a> {
         self.bomb_state = State::Dormant;
     }
 
+    // how often each type drops, see attacks.rs
+    pub fn set_weights(&mut self, weights: [u32; BOMB_TYPES]) {
+        self.weights = weights;
+    }
+
     pub fn launch(&mut self, x: i32) {
         self.x = x - BOMB_WIDTH as i32 / 2;
         self.y = START_Y;
+        self.prev_y = START_Y;
         self.bomb_state = State::InFlight;
-        self.bomb_type =
-            match rand::thread_rng().gen_range(0..3) {
-                0 => {BombType::Bonus10},
-                1 => {BombType::Bonus30},
-                _ => {BombType::Bonus50},
-            };
+        let total: u32 = self.weights.iter().sum();
+        let mut n = common::random_range(0..total.max(1));
+        let i = self.weights.iter()
+            .position(|&w| if n < w {true} else {n -= w; false})
+            .unwrap_or(0);
+        self.bomb_type = BombType::from_index(i);
     }
 
-    pub fn collision(&mut self, col_area: ScreenObjectArea) -> bool {
-        self.in_flight() && col_area.collides(self.area())
+    pub fn collision(&mut self, hitbox: &Hitbox) -> bool {
+        self.in_flight() && self.hitbox().collides(hitbox)
     }
 
-    pub fn achieve_bonus(&mut self, letter_bricks: &mut LetterBricks, animations: &mut Animations) {
+    pub fn achieve_bonus(&mut self, letter_bricks: &mut LetterBricks, power_ups: &mut PowerUps,
+        animations: &mut Animations, effects: &mut ScreenEffects) {
         self.bomb_state = State::Dormant;
-        letter_bricks.initiate_removal(BOMB_VALUE[self.bomb_type as usize]);
+        match self.bomb_type.power_up() {
+            Some(power_up) => {power_ups.grant(power_up);},
+            None => {
+                letter_bricks.initiate_removal(BOMB_VALUE[self.bomb_type as usize]);
+                self.bonus_bomb_hit_sound.play();
+            },
+        }
 
         let x = self.x + BOMB_WIDTH as i32 / 2;
         let y = self.y + BOMB_HEIGHT as i32 / 2;
         let bomb_type = self.bomb_type as usize;
         let animation = Animation::new(AnimationSeq::BonusBombHit(x, y, bomb_type));
         animations.register(animation);
-        self.bonus_bomb_hit_sound.play();
+        effects.add_trauma(0.5);
+        effects.hit_stop(6);
+        effects.flash(255, 255, 255);
     }
 
     pub fn score(&self) -> u32 {
-        BOMB_VALUE[self.bomb_type as usize] as u32 * SCORE_MULTIPLIER
+        match self.bomb_type.power_up() {
+            Some(_) => {POWER_UP_SCORE},
+            None => {BOMB_VALUE[self.bomb_type as usize] as u32 * SCORE_MULTIPLIER},
+        }
     }
 
     pub fn turn_sound_on(&mut self) {
@@ -116,6 +210,7 @@ impl<'a> BonusBomb<'a> {
     }
 
     pub fn update(&mut self) {
+        self.prev_y = self.y;
         match self.bomb_state {
             State::InFlight => {
                 if (self.y - START_Y) % 120 == 0 && self.sound_on {
@@ -130,6 +225,28 @@ impl<'a> BonusBomb<'a> {
         }
     }
 
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        (self.x, self.y, self.in_flight(), self.bomb_type as usize).hash(state);
+    }
+
+    pub fn describe(&self) -> String {
+        if self.in_flight() {format!("{}:{}:{}", self.x, self.y, self.bomb_type as usize)}
+        else {String::from("-")}
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        if fields.len() == 3 {
+            self.x = spectator::field(&fields, 0, self.x);
+            self.y = spectator::field(&fields, 1, self.y);
+            self.bomb_type = BombType::from_index(spectator::field(&fields, 2, 0));
+            self.bomb_state = State::InFlight;
+        }
+        else {
+            self.bomb_state = State::Dormant;
+        }
+    }
+
     pub fn render(&self, canvas: &mut Canvas<Window>, frame_count: u32) {
         let bt = self.bomb_type as usize;
         let an = ((frame_count / BOMB_PERIOD) % 2) as usize;
@@ -141,4 +258,11 @@ impl<'a> BonusBomb<'a> {
             _ => {},
         }
     }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        if self.in_flight() {
+            self.hitbox().render_debug(canvas, Color::RGB(255, 0, 255));
+        }
+    }
 }
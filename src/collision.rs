@@ -0,0 +1,240 @@
+// collision shapes that can be more accurate than a simple box
+
+use sdl2::image::LoadSurface;
+use sdl2::surface::Surface;
+use sdl2::pixels::PixelFormatEnum;
+#[cfg(feature = "debug-overlay")]
+use sdl2::video::Window;
+#[cfg(feature = "debug-overlay")]
+use sdl2::render::Canvas;
+#[cfg(feature = "debug-overlay")]
+use sdl2::rect::Point;
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
+
+use crate::common::{self, ScreenObjectArea};
+
+const ALPHA_THRESHOLD: u8 = 128;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum CollisionMode {
+    Aabb,
+    RotatedBox,
+    Mask,
+}
+
+impl CollisionMode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            CollisionMode::Aabb => {"aabb"},
+            CollisionMode::RotatedBox => {"box"},
+            CollisionMode::Mask => {"mask"},
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CollisionMode> {
+        match name {
+            "aabb" => {Some(CollisionMode::Aabb)},
+            "box" => {Some(CollisionMode::RotatedBox)},
+            "mask" => {Some(CollisionMode::Mask)},
+            _ => {None},
+        }
+    }
+}
+
+pub struct CollisionMask {
+    width: usize,
+    height: usize,
+    solid: Vec<bool>,
+}
+
+impl CollisionMask {
+    // the mask is the union of the opaque parts of all the given images so
+    // that one mask can cover every frame of an animation
+    pub fn new(file_names: &[&str]) -> CollisionMask {
+        let mut mask = CollisionMask {width: 0, height: 0, solid: Vec::new()};
+        for file_name in file_names {
+            let image_path = common::find_asset(&format!("image/{}", file_name));
+            let surface = Surface::from_file(image_path).unwrap()
+                .convert_format(PixelFormatEnum::RGBA32).unwrap();
+            let width = surface.width() as usize;
+            let height = surface.height() as usize;
+            let pitch = surface.pitch() as usize;
+            if mask.solid.is_empty() {
+                mask.width = width;
+                mask.height = height;
+                mask.solid = vec![false; width * height];
+            }
+            if width != mask.width || height != mask.height {
+                panic!("Collision mask image {} does not match size of others", file_name);
+            }
+            surface.with_lock(|pixels| {
+                for y in 0..height {
+                    for x in 0..width {
+                        if pixels[y * pitch + x * 4 + 3] >= ALPHA_THRESHOLD {
+                            mask.solid[y * width + x] = true;
+                        }
+                    }
+                }
+            });
+        }
+        mask
+    }
+
+    // only the part of the mask between the given fractions of its width,
+    // for when just the middle of a sprite should collide
+    pub fn cropped(self, left: f64, right: f64) -> CollisionMask {
+        let from = (left * self.width as f64) as usize;
+        let to = ((right * self.width as f64) as usize).clamp(from, self.width);
+        let mut solid = Vec::with_capacity((to - from) * self.height);
+        for y in 0..self.height {
+            solid.extend_from_slice(&self.solid[y * self.width + from..y * self.width + to]);
+        }
+        CollisionMask {width: to - from, height: self.height, solid}
+    }
+
+    // u and v run from 0.0 to 1.0 across and down the image, so the mask
+    // works whatever size the image is rendered at
+    fn solid_at(&self, u: f64, v: f64) -> bool {
+        let x = (u * self.width as f64) as usize;
+        let y = (v * self.height as f64) as usize;
+        x < self.width && y < self.height && self.solid[y * self.width + x]
+    }
+}
+
+// the collidable shape of an object, the area is where it would be rendered
+// (already scaled) and the angle is in degrees clockwise about the centre of
+// the area, the same as sdl uses when rendering
+pub struct Hitbox<'m> {
+    area: ScreenObjectArea,
+    angle: f64,
+    mode: CollisionMode,
+    mask: Option<&'m CollisionMask>,
+}
+
+impl<'m> Hitbox<'m> {
+    pub fn new(area: ScreenObjectArea) -> Hitbox<'m> {
+        Hitbox {area, angle: 0.0, mode: CollisionMode::Aabb, mask: None}
+    }
+
+    pub fn rotated(self, angle: f64) -> Hitbox<'m> {
+        Hitbox {angle, ..self}
+    }
+
+    // for things without a mask, which collide as a rotated box in mask mode
+    pub fn mode(self, mode: CollisionMode) -> Hitbox<'m> {
+        Hitbox {mode, ..self}
+    }
+
+    pub fn shaped(self, mode: CollisionMode, mask: &'m CollisionMask) -> Hitbox<'m> {
+        Hitbox {mode, mask: Some(mask), ..self}
+    }
+
+    // top left of the area at time t through the last frame, 0.0 being the
+    // start of the frame and 1.0 being now
+    fn position_at(&self, t: f64) -> (f64, f64) {
+        let (x, y) = self.area.position();
+        let (dx, dy) = self.area.motion();
+        (x as f64 - dx as f64 * (1.0 - t), y as f64 - dy as f64 * (1.0 - t))
+    }
+
+    fn half_size(&self) -> (f64, f64) {
+        let (w, h) = self.area.size();
+        (w as f64 * 0.5, h as f64 * 0.5)
+    }
+
+    fn rotation(&self) -> f64 {
+        // an aabb ignores any rotation, which is how things used to be
+        if self.mode == CollisionMode::Aabb {0.0} else {self.angle.to_radians()}
+    }
+
+    // min x, min y, max x, max y of the shape when its top left is at x, y
+    fn bounds(&self, x: f64, y: f64) -> (f64, f64, f64, f64) {
+        let (hw, hh) = self.half_size();
+        let (sin, cos) = self.rotation().sin_cos();
+        let ex = (hw * cos).abs() + (hh * sin).abs();
+        let ey = (hw * sin).abs() + (hh * cos).abs();
+        (x + hw - ex, y + hh - ey, x + hw + ex, y + hh + ey)
+    }
+
+    fn contains(&self, x: f64, y: f64, px: f64, py: f64) -> bool {
+        let (hw, hh) = self.half_size();
+        let (sin, cos) = self.rotation().sin_cos();
+        // rotate the point back into the frame of the unrotated shape
+        let dx = px - (x + hw);
+        let dy = py - (y + hh);
+        let lx = dx * cos + dy * sin;
+        let ly = - dx * sin + dy * cos;
+        if lx < - hw || lx >= hw || ly < - hh || ly >= hh {
+            return false;
+        }
+        match (self.mode, self.mask) {
+            (CollisionMode::Mask, Some(mask)) => {
+                mask.solid_at((lx + hw) / (2.0 * hw), (ly + hh) / (2.0 * hh))
+            },
+            _ => {true},
+        }
+    }
+
+    // a box around everything the shape passed through during the frame
+    fn bounding_area(&self) -> ScreenObjectArea {
+        let (x, y) = self.position_at(1.0);
+        let (min_x, min_y, max_x, max_y) = self.bounds(x, y);
+        let (dx, dy) = self.area.motion();
+        ScreenObjectArea::new(min_x.floor() as i32, min_y.floor() as i32,
+            (max_x.ceil() - min_x.floor()) as u32, (max_y.ceil() - min_y.floor()) as u32)
+            .moving(dx, dy)
+    }
+
+    // does any pixel belong to both shapes at time t?
+    fn overlaps_at(&self, other: &Hitbox, t: f64) -> bool {
+        let (sx, sy) = self.position_at(t);
+        let (ox, oy) = other.position_at(t);
+        let (s_min_x, s_min_y, s_max_x, s_max_y) = self.bounds(sx, sy);
+        let (o_min_x, o_min_y, o_max_x, o_max_y) = other.bounds(ox, oy);
+        let min_x = s_min_x.max(o_min_x).floor() as i32;
+        let min_y = s_min_y.max(o_min_y).floor() as i32;
+        let max_x = s_max_x.min(o_max_x).ceil() as i32;
+        let max_y = s_max_y.min(o_max_y).ceil() as i32;
+        for py in min_y..max_y {
+            for px in min_x..max_x {
+                let cx = px as f64 + 0.5;
+                let cy = py as f64 + 0.5;
+                if self.contains(sx, sy, cx, cy) && other.contains(ox, oy, cx, cy) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn collides(&self, other: &Hitbox) -> bool {
+        // the swept boxes are exact for two aabbs, otherwise they rule out
+        // most cases before we do anything expensive
+        if ! self.bounding_area().collides(other.bounding_area()) {
+            return false;
+        }
+        if self.mode == CollisionMode::Aabb && other.mode == CollisionMode::Aabb {
+            return true;
+        }
+        // step through the frame a pixel of relative movement at a time
+        let (s_dx, s_dy) = self.area.motion();
+        let (o_dx, o_dy) = other.area.motion();
+        let steps = (s_dx - o_dx).abs().max((s_dy - o_dy).abs()).max(1);
+        (0..steps + 1).any(|i| self.overlaps_at(other, i as f64 / steps as f64))
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>, colour: Color) {
+        let (x, y) = self.position_at(1.0);
+        let (hw, hh) = self.half_size();
+        let (sin, cos) = self.rotation().sin_cos();
+        let corners: Vec<Point> = [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh), (-hw, -hh)].iter()
+            .map(|&(lx, ly)| Point::new((x + hw + lx * cos - ly * sin) as i32,
+                                        (y + hh + lx * sin + ly * cos) as i32))
+            .collect();
+        canvas.set_draw_color(colour);
+        canvas.draw_lines(corners.as_slice()).unwrap();
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+    }
+}
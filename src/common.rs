@@ -1,5 +1,13 @@
 use std::path::PathBuf;
+use std::env;
+use std::fs;
+use std::cell::RefCell;
 use find_folder;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::distributions::uniform::{SampleRange, SampleUniform};
+#[cfg(feature = "debug-overlay")]
+use sdl2::rect::Rect;
 
 pub const SCREEN_WIDTH: u32 = 600;
 pub const SCREEN_HEIGHT: u32 = 700;
@@ -12,26 +20,178 @@ pub struct TargetBrick {
     pub brick_id: usize,
 }
 
+// an area is where an object is now, along with how far it moved to get
+// there during the last frame
 pub struct ScreenObjectArea {
     tl_x: i32,
     tl_y: i32,
     br_x: i32,
     br_y: i32,
+    dx: i32,
+    dy: i32,
+}
+
+// the range of time (as a fraction of a frame) that two spans overlap on one
+// axis, span b moving at vel relative to span a
+fn overlap_time(a_min: i32, a_max: i32, b_min: i32, b_max: i32, vel: i32) -> Option<(f64, f64)> {
+    if vel == 0 {
+        if b_min < a_max && b_max > a_min {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        }
+        else {
+            None
+        }
+    }
+    else {
+        let t1 = (a_min - b_max) as f64 / vel as f64;
+        let t2 = (a_max - b_min) as f64 / vel as f64;
+        Some((t1.min(t2), t1.max(t2)))
+    }
 }
 
 impl ScreenObjectArea {
     pub fn new(x: i32, y: i32, w: u32, h: u32) -> ScreenObjectArea {
-        ScreenObjectArea {tl_x: x, tl_y: y, br_x: x + w as i32, br_y: y + h as i32}
+        ScreenObjectArea {tl_x: x, tl_y: y, br_x: x + w as i32, br_y: y + h as i32, dx: 0, dy: 0}
+    }
+
+    pub fn moving(self, dx: i32, dy: i32) -> ScreenObjectArea {
+        ScreenObjectArea {dx, dy, ..self}
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.tl_x, self.tl_y)
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        ((self.br_x - self.tl_x) as u32, (self.br_y - self.tl_y) as u32)
     }
 
+    pub fn motion(&self) -> (i32, i32) {
+        (self.dx, self.dy)
+    }
+
+    // fast, thin objects can pass straight through each other between frames
+    // so rather than checking for overlap at the end of the frame, we sweep
+    // both areas along their movement and check for overlap at any point
     pub fn collides(&self, col_area: ScreenObjectArea) -> bool {
-        col_area.br_x > self.tl_x && col_area.tl_x < self.br_x &&
-        col_area.br_y > self.tl_y && col_area.tl_y < self.br_y
+        // work relative to where self started the frame
+        let vel_x = col_area.dx - self.dx;
+        let vel_y = col_area.dy - self.dy;
+        let (a_tl_x, a_br_x) = (self.tl_x - self.dx, self.br_x - self.dx);
+        let (a_tl_y, a_br_y) = (self.tl_y - self.dy, self.br_y - self.dy);
+        let (b_tl_x, b_br_x) = (col_area.tl_x - col_area.dx, col_area.br_x - col_area.dx);
+        let (b_tl_y, b_br_y) = (col_area.tl_y - col_area.dy, col_area.br_y - col_area.dy);
+
+        match (overlap_time(a_tl_x, a_br_x, b_tl_x, b_br_x, vel_x),
+               overlap_time(a_tl_y, a_br_y, b_tl_y, b_br_y, vel_y)) {
+            (Some((x_enter, x_exit)), Some((y_enter, y_exit))) => {
+                let enter = x_enter.max(y_enter).max(0.0);
+                let exit = x_exit.min(y_exit).min(1.0);
+                enter < exit
+            },
+            _ => {false},
+        }
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.tl_x, self.tl_y, (self.br_x - self.tl_x) as u32, (self.br_y - self.tl_y) as u32)
     }
 }
 
+thread_local! {
+    // everything that decides how a game plays out takes its random numbers
+    // from here, so that the same seed and the same inputs always give the
+    // same game
+    static GAME_RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn random_range<T: SampleUniform, R: SampleRange<T>>(range: R) -> T {
+    GAME_RNG.with(|rng| rng.borrow_mut().gen_range(range))
+}
+
+pub fn random_bool() -> bool {
+    GAME_RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+pub fn new_random(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+// a game keeps its own generator and swaps it in while it updates, so that
+// two games can run side by side without sharing one sequence
+pub fn swap_random(rng: &mut StdRng) {
+    GAME_RNG.with(|game_rng| std::mem::swap(&mut *game_rng.borrow_mut(), rng));
+}
+
 pub fn find_asset(file_name: &str) -> PathBuf {
     let assets = find_folder::Search::ParentsThenKids(3, 3).for_folder("assets").unwrap();
     assets.join(file_name)
 }
 
+// somewhere to keep files that should survive between runs, following the
+// XDG convention where we can
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    let fin_dir = config_dir.join("fin-sdl");
+    fs::create_dir_all(&fin_dir).ok()?;
+    Some(fin_dir.join(file_name))
+}
+
+// minutes and seconds, as shown in the HUD and the high score tables
+pub fn format_time(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the check from before areas knew how they moved
+    fn overlaps(a: &ScreenObjectArea, b: &ScreenObjectArea) -> bool {
+        b.br_x > a.tl_x && b.tl_x < a.br_x && b.br_y > a.tl_y && b.tl_y < a.br_y
+    }
+
+    #[test]
+    fn fast_missile_passes_through_spider() {
+        // the missile starts the frame below the spider and ends it above
+        let spider = ScreenObjectArea::new(100, 100, 30, 20);
+        let missile = ScreenObjectArea::new(110, 60, 3, 10).moving(0, -60);
+        assert!(! overlaps(&spider, &missile));
+        assert!(spider.collides(missile));
+    }
+
+    #[test]
+    fn fast_missile_near_miss() {
+        let spider = ScreenObjectArea::new(100, 100, 30, 20);
+        let missile = ScreenObjectArea::new(131, 60, 3, 10).moving(0, -60);
+        assert!(! spider.collides(missile));
+        // going diagonally away from the corner without touching it
+        let spider = ScreenObjectArea::new(100, 100, 30, 20);
+        let missile = ScreenObjectArea::new(155, 60, 3, 10).moving(20, -60);
+        assert!(! spider.collides(missile));
+    }
+
+    #[test]
+    fn moving_spider_and_missile() {
+        // the spider moves across the missile's path as the missile goes up
+        let spider = ScreenObjectArea::new(130, 100, 30, 20).moving(30, 0);
+        let missile = ScreenObjectArea::new(110, 60, 3, 10).moving(0, -60);
+        assert!(spider.collides(missile));
+    }
+
+    #[test]
+    fn zero_velocity_matches_overlap() {
+        let spider = ScreenObjectArea::new(100, 100, 30, 20);
+        for x in 80..140 {
+            for y in 80..130 {
+                let missile = ScreenObjectArea::new(x, y, 3, 10);
+                let expected = overlaps(&spider, &missile);
+                assert_eq!(spider.collides(missile), expected, "missile at {}, {}", x, y);
+            }
+        }
+    }
+}
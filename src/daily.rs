@@ -0,0 +1,268 @@
+// the daily challenge - a one player game whose seed and mutators come from
+// the date, so everyone playing on the same day gets the same game without
+// needing a server. Each result is kept in a file for the day along with a
+// replay of the controls, which `fin-sdl --replay file` plays back
+//
+// a result is a line of date score seconds replay, where the replay is
+// controls:frames for each run of frames with the same controls, separated
+// by ;
+
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use rand::Rng;
+
+use crate::common;
+use crate::spectator;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+// how likely each mutator is to be on for the day
+const MUTATOR_CHANCE: f64 = 0.4;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Mutators {
+    pub fast_spiders: bool,
+    pub one_life: bool,
+    pub no_extra_lives: bool,
+    pub double_bombs: bool,
+}
+
+impl Mutators {
+    pub fn none() -> Mutators {
+        Mutators {fast_spiders: false, one_life: false, no_extra_lives: false, double_bombs: false}
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [(self.fast_spiders, "fast spiders"), (self.one_life, "one life"),
+         (self.no_extra_lives, "no extra lives"), (self.double_bombs, "double bombs")].iter()
+            .filter(|(on, _)| *on)
+            .map(|&(_, name)| name)
+            .collect()
+    }
+}
+
+pub struct Challenge {
+    pub date: String,
+    pub seed: u64,
+    pub mutators: Mutators,
+}
+
+impl Challenge {
+    pub fn today() -> Challenge {
+        Challenge::for_date(&today())
+    }
+
+    pub fn for_date(date: &str) -> Challenge {
+        let seed = seed_from_date(date);
+        let mut rng = common::new_random(seed);
+        let mut mutators = Mutators {
+            fast_spiders: rng.gen_bool(MUTATOR_CHANCE),
+            one_life: rng.gen_bool(MUTATOR_CHANCE),
+            no_extra_lives: rng.gen_bool(MUTATOR_CHANCE),
+            double_bombs: rng.gen_bool(MUTATOR_CHANCE),
+        };
+        // every day has at least one
+        if mutators == Mutators::none() {
+            match rng.gen_range(0..4) {
+                0 => {mutators.fast_spiders = true;},
+                1 => {mutators.one_life = true;},
+                2 => {mutators.no_extra_lives = true;},
+                _ => {mutators.double_bombs = true;},
+            }
+        }
+        Challenge {date: date.to_string(), seed, mutators}
+    }
+}
+
+// the UTC date as yyyy-mm-dd, so the day turns over at the same moment for
+// everyone and they all play the same game at the same time
+fn today() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// days since 1970-01-01 to a year, month and day in the Gregorian calendar,
+// see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 {mp + 3} else {mp - 9} as u32;
+    let year = yoe + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
+
+// FNV-1a, which unlike the standard library's hasher is the same on every
+// build so everyone gets the same seed
+fn seed_from_date(date: &str) -> u64 {
+    date.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+// the controls for every frame of a game, as Game::take_local_controls
+// packs them
+pub struct Replay {
+    controls: Vec<u8>,
+    next: usize,
+}
+
+impl Replay {
+    pub fn new() -> Replay {
+        Replay {controls: Vec::new(), next: 0}
+    }
+
+    pub fn record(&mut self, controls: u8) {
+        self.controls.push(controls);
+    }
+
+    // nothing is pressed once the replay runs out
+    pub fn next_controls(&mut self) -> u8 {
+        let controls = self.controls.get(self.next).copied().unwrap_or(0);
+        self.next += 1;
+        controls
+    }
+
+    fn encode(&self) -> String {
+        let mut runs: Vec<(u8, u32)> = Vec::new();
+        for &controls in &self.controls {
+            match runs.last_mut() {
+                Some((c, n)) if *c == controls => {*n += 1;},
+                _ => {runs.push((controls, 1));},
+            }
+        }
+        let runs: Vec<String> = runs.iter().map(|(c, n)| format!("{}:{}", c, n)).collect();
+        spectator::list(&runs)
+    }
+
+    fn decode(description: &str) -> Replay {
+        let mut replay = Replay::new();
+        for run in spectator::items(description) {
+            let fields: Vec<&str> = run.split(':').collect();
+            let controls: u8 = spectator::field(&fields, 0, 0);
+            let frames: u32 = spectator::field(&fields, 1, 0);
+            replay.controls.extend((0..frames).map(|_| controls));
+        }
+        replay
+    }
+}
+
+pub struct DailyResult {
+    pub date: String,
+    pub score: u32,
+    pub seconds: u32,
+    replay: String,
+}
+
+impl DailyResult {
+    fn parse(line: &str) -> Option<DailyResult> {
+        let fields: Vec<&str> = line.split(' ').collect();
+        if fields.len() != 4 {
+            return None;
+        }
+        Some(DailyResult {date: fields[0].to_string(), score: fields[1].parse().ok()?,
+            seconds: fields[2].parse().ok()?, replay: fields[3].to_string()})
+    }
+}
+
+fn results_file(date: &str) -> String {
+    format!("daily-{}.txt", date)
+}
+
+// the results for the day, best first
+pub fn load_results(date: &str) -> Vec<DailyResult> {
+    let contents = common::config_path(&results_file(date))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    parse_results(&contents)
+}
+
+fn parse_results(contents: &str) -> Vec<DailyResult> {
+    let mut results: Vec<DailyResult> = contents.lines().filter_map(DailyResult::parse).collect();
+    results.sort_by_key(|r| std::cmp::Reverse(r.score));
+    results
+}
+
+pub fn save_result(challenge: &Challenge, score: u32, seconds: u32, replay: &Replay) {
+    let mut results = load_results(&challenge.date);
+    results.push(DailyResult {date: challenge.date.clone(), score, seconds, replay: replay.encode()});
+    results.sort_by_key(|r| std::cmp::Reverse(r.score));
+    let contents: String = results.iter()
+        .map(|r| format!("{} {} {} {}\n", r.date, r.score, r.seconds, r.replay))
+        .collect();
+    // losing a result is a shame but no reason to stop the game
+    if let Some(path) = common::config_path(&results_file(&challenge.date)) {
+        let _res = fs::write(path, contents);
+    }
+}
+
+// the best game in a results file, which may have come from someone else
+pub fn load_replay(file_name: &str) -> Result<(Challenge, Replay), String> {
+    let contents = fs::read_to_string(file_name).map_err(|e| format!("Can't open {}: {}", file_name, e))?;
+    let best = parse_results(&contents).into_iter().next()
+        .ok_or_else(|| format!("No daily results in {}", file_name))?;
+    Ok((Challenge::for_date(&best.date), Replay::decode(&best.replay)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        // a leap day, and the turn of a century that is a leap year
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(10_957), (2000, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(20_745), (2026, 10, 19));
+    }
+
+    #[test]
+    fn seeds_are_the_same_everywhere() {
+        // FNV-1a of nothing is the offset basis
+        assert_eq!(seed_from_date(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_date("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(seed_from_date("2026-10-19"), seed_from_date("2026-10-19"));
+        assert_ne!(seed_from_date("2026-10-19"), seed_from_date("2026-10-20"));
+    }
+
+    #[test]
+    fn challenges_come_from_the_date() {
+        let challenge = Challenge::for_date("2026-10-19");
+        let again = Challenge::for_date("2026-10-19");
+        assert_eq!(challenge.seed, again.seed);
+        assert!(challenge.mutators == again.mutators);
+        assert!(! challenge.mutators.names().is_empty());
+    }
+
+    #[test]
+    fn replays_survive_being_saved() {
+        let mut replay = Replay::new();
+        for controls in [0, 0, 0, 5, 5, 1, 0, 0, 12] {
+            replay.record(controls);
+        }
+        let encoded = replay.encode();
+        assert_eq!(encoded, "0:3;5:2;1:1;0:2;12:1");
+        let mut decoded = Replay::decode(&encoded);
+        assert_eq!(decoded.controls, replay.controls);
+        assert_eq!(decoded.next_controls(), 0);
+        // nothing is pressed after the end
+        for _ in 0..8 {
+            decoded.next_controls();
+        }
+        assert_eq!(decoded.next_controls(), 0);
+        assert!(Replay::decode(&Replay::new().encode()).controls.is_empty());
+    }
+
+    #[test]
+    fn results_are_best_first() {
+        let results = parse_results("2026-10-19 120 60 0:10\nbad line\n2026-10-19 450 90 0:20\n");
+        let scores: Vec<u32> = results.iter().map(|r| r.score).collect();
+        assert_eq!(scores, [450, 120]);
+    }
+}
@@ -0,0 +1,157 @@
+// debug overlay - only compiled in with the debug-overlay feature
+
+use std::collections::VecDeque;
+use std::time::Duration;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::video::Window;
+use sdl2::render::Canvas;
+use sdl2::rect::{Point, Rect};
+use sdl2::pixels::Color;
+use sdl2::ttf::Sdl2TtfContext;
+
+use crate::common::{ScreenObjectArea, UPDATE_FPS};
+use crate::text::Text;
+use crate::paths::FORMATIONS;
+
+const GRAPH_SAMPLES: usize = 120;
+const GRAPH_X: i32 = 5;
+const GRAPH_Y: i32 = 45;
+const GRAPH_HEIGHT: i32 = 50;
+// full height of graph represents two frames worth of time
+const GRAPH_SCALE_MS: f64 = 2000.0 / UPDATE_FPS as f64;
+// formation previews are flown from here, with a pause at the end
+const PREVIEW_ORIGIN: (f64, f64) = (150.0, 110.0);
+const PREVIEW_PAUSE_FRAMES: u32 = 30;
+
+pub struct DebugOverlay<'a, 'b> {
+    enabled: bool,
+    frame_ms: VecDeque<f64>,
+    update_ms: VecDeque<f64>,
+    text: Text<'a, 'b>,
+    // the formation being previewed, chosen with F11
+    preview: Option<usize>,
+}
+
+impl<'a, 'b> DebugOverlay<'a, 'b> {
+    pub fn new(ttf_context: &'a Sdl2TtfContext) -> DebugOverlay<'a, 'b> {
+        DebugOverlay {
+            enabled: false,
+            frame_ms: VecDeque::with_capacity(GRAPH_SAMPLES),
+            update_ms: VecDeque::with_capacity(GRAPH_SAMPLES),
+            text: Text::new(ttf_context, 12),
+            preview: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn text(&self) -> &Text<'a, 'b> {
+        &self.text
+    }
+
+    pub fn update_inputs(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown {keycode: Some(Keycode::F12), repeat: false, ..} => {
+                self.enabled = ! self.enabled;
+            },
+            Event::KeyDown {keycode: Some(Keycode::F11), repeat: false, ..} => {
+                self.preview = match self.preview {
+                    None => {Some(0)},
+                    Some(f) if f < FORMATIONS.len() - 1 => {Some(f + 1)},
+                    Some(_) => {None},
+                };
+            },
+            _ => {},
+        }
+    }
+
+    pub fn record_timing(&mut self, frame_time: Duration, update_time: Duration) {
+        if self.frame_ms.len() == GRAPH_SAMPLES {
+            self.frame_ms.pop_front();
+            self.update_ms.pop_front();
+        }
+        self.frame_ms.push_back(frame_time.as_secs_f64() * 1000.0);
+        self.update_ms.push_back(update_time.as_secs_f64() * 1000.0);
+    }
+
+    fn render_graph(canvas: &mut Canvas<Window>, samples: &VecDeque<f64>, colour: Color) {
+        canvas.set_draw_color(colour);
+        let points: Vec<Point> = samples.iter().enumerate()
+            .map(|(i, ms)| {
+                let h = ((ms / GRAPH_SCALE_MS) * GRAPH_HEIGHT as f64).min(GRAPH_HEIGHT as f64);
+                Point::new(GRAPH_X + 2 * i as i32, GRAPH_Y + GRAPH_HEIGHT - h as i32)
+            })
+            .collect();
+        let _ = canvas.draw_lines(points.as_slice());
+    }
+
+    pub fn render(&self, canvas: &mut Canvas<Window>) {
+        if ! self.enabled {
+            return;
+        }
+
+        // graph background and a line marking the target frame time
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        canvas.fill_rect(Rect::new(GRAPH_X, GRAPH_Y, 2 * GRAPH_SAMPLES as u32, GRAPH_HEIGHT as u32)).unwrap();
+        canvas.set_draw_color(Color::RGBA(80, 80, 80, 255));
+        let target_y = GRAPH_Y + GRAPH_HEIGHT / 2;
+        canvas.draw_line(Point::new(GRAPH_X, target_y),
+            Point::new(GRAPH_X + 2 * GRAPH_SAMPLES as i32, target_y)).unwrap();
+
+        DebugOverlay::render_graph(canvas, &self.frame_ms, Color::RGB(0, 255, 0));
+        DebugOverlay::render_graph(canvas, &self.update_ms, Color::RGB(255, 128, 0));
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+
+        let n = self.frame_ms.len().max(1) as f64;
+        let avg_frame_ms = self.frame_ms.iter().sum::<f64>() / n;
+        let avg_update_ms = self.update_ms.iter().sum::<f64>() / n;
+        let fps = if avg_frame_ms > 0.0 {1000.0 / avg_frame_ms} else {0.0};
+        self.text.render(canvas, GRAPH_X, GRAPH_Y + GRAPH_HEIGHT + 2, 0, 255, 0, 255,
+            &format!("fps {:.1}", fps));
+        self.text.render(canvas, GRAPH_X + 80, GRAPH_Y + GRAPH_HEIGHT + 2, 255, 128, 0, 255,
+            &format!("update {:.2}ms", avg_update_ms));
+    }
+
+    // the path of the formation and each of its slots flying along it
+    pub fn render_preview(&self, canvas: &mut Canvas<Window>, frame_count: u32) {
+        let formation = match self.preview {
+            Some(f) => {&FORMATIONS[f]},
+            None => {return;},
+        };
+        let path = formation.path();
+        let (origin_x, origin_y) = PREVIEW_ORIGIN;
+        let points: Vec<Point> = (0..=path.frames())
+            .map(|frame| {
+                let (x, y) = path.position(frame);
+                Point::new((origin_x + x) as i32, (origin_y + y) as i32)
+            })
+            .collect();
+        canvas.set_draw_color(Color::RGBA(255, 255, 0, 255));
+        let _ = canvas.draw_lines(points.as_slice());
+        let frame = (frame_count % (path.frames() + PREVIEW_PAUSE_FRAMES)).min(path.frames());
+        for slot in 0..formation.slots.len() {
+            let (x, y) = formation.position(frame, slot);
+            let _ = canvas.fill_rect(Rect::new((origin_x + x) as i32 - 3, (origin_y + y) as i32 - 3, 6, 6));
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.text.render(canvas, origin_x as i32, origin_y as i32 - 20, 255, 255, 0, 255,
+            &format!("{} on {}", formation.name, path.name));
+    }
+}
+
+pub fn render_area(canvas: &mut Canvas<Window>, area: &ScreenObjectArea, colour: Color) {
+    canvas.set_draw_color(colour);
+    canvas.draw_rect(area.rect()).unwrap();
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+}
+
+pub fn render_vector(canvas: &mut Canvas<Window>, x: f64, y: f64, x_vel: f64, y_vel: f64, colour: Color) {
+    // exaggerate the velocity so that it can be seen
+    canvas.set_draw_color(colour);
+    canvas.draw_line(Point::new(x as i32, y as i32),
+        Point::new((x + x_vel * 10.0) as i32, (y + y_vel * 10.0) as i32)).unwrap();
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+}
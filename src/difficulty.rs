@@ -0,0 +1,54 @@
+// difficulty levels, normal is the game as it has always been played
+
+// how spiders get out of the way of missiles, only on hard do they see
+// them coming at all
+pub struct Evasion {
+    // the chance of a spider noticing a missile heading for it
+    pub chance: f64,
+    pub reaction_frames: u32,
+    pub sidestep_speed: f64,
+}
+
+const EVASION: [Evasion; 3] = [
+    Evasion {chance: 0.0, reaction_frames: 0, sidestep_speed: 0.0},
+    Evasion {chance: 0.0, reaction_frames: 0, sidestep_speed: 0.0},
+    Evasion {chance: 0.5, reaction_frames: 6, sidestep_speed: 4.0},
+];
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => {"Easy"},
+            Difficulty::Normal => {"Normal"},
+            Difficulty::Hard => {"Hard"},
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "Easy" => {Some(Difficulty::Easy)},
+            "Normal" => {Some(Difficulty::Normal)},
+            "Hard" => {Some(Difficulty::Hard)},
+            _ => {None},
+        }
+    }
+
+    pub fn evasion(&self) -> &'static Evasion {
+        &EVASION[*self as usize]
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match *self {
+            Difficulty::Easy => {Difficulty::Normal},
+            Difficulty::Normal => {Difficulty::Hard},
+            Difficulty::Hard => {Difficulty::Easy},
+        }
+    }
+}
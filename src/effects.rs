@@ -0,0 +1,101 @@
+// screen wide effects - camera shake, hit-stop and flashes
+
+use rand::Rng;
+use sdl2::video::Window;
+use sdl2::render::Canvas;
+use sdl2::rect::Rect;
+use sdl2::pixels::Color;
+
+use crate::common::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::options::Options;
+
+const MAX_SHAKE_OFFSET: f64 = 10.0;
+const TRAUMA_DECAY: f64 = 0.02;
+const FLASH_FRAMES: u32 = 12;
+const FLASH_MAX_ALPHA: u32 = 160;
+
+pub struct ScreenEffects {
+    trauma: f64,
+    offset_x: i32,
+    offset_y: i32,
+    hit_stop_frames: u32,
+    flash_colour: (u8, u8, u8),
+    flash_frames_left: u32,
+}
+
+impl ScreenEffects {
+    pub fn new() -> ScreenEffects {
+        ScreenEffects {
+            trauma: 0.0,
+            offset_x: 0,
+            offset_y: 0,
+            hit_stop_frames: 0,
+            flash_colour: (255, 255, 255),
+            flash_frames_left: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.trauma = 0.0;
+        self.offset_x = 0;
+        self.offset_y = 0;
+        self.hit_stop_frames = 0;
+        self.flash_frames_left = 0;
+    }
+
+    // trauma accumulates up to 1.0 and decays over time, the amount of shake
+    // is the square of it so that small knocks are barely noticed
+    pub fn add_trauma(&mut self, trauma: f64) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    pub fn hit_stop(&mut self, frames: u32) {
+        self.hit_stop_frames = self.hit_stop_frames.max(frames);
+    }
+
+    pub fn flash(&mut self, r: u8, g: u8, b: u8) {
+        self.flash_colour = (r, g, b);
+        self.flash_frames_left = FLASH_FRAMES;
+    }
+
+    pub fn hit_stopped(&self, options: &Options) -> bool {
+        options.hit_stop && self.hit_stop_frames > 0
+    }
+
+    pub fn update(&mut self, options: &Options) {
+        if self.hit_stop_frames > 0 {
+            self.hit_stop_frames -= 1;
+        }
+        if self.flash_frames_left > 0 {
+            self.flash_frames_left -= 1;
+        }
+        self.trauma = (self.trauma - TRAUMA_DECAY).max(0.0);
+        if options.screen_shake && self.trauma > 0.0 {
+            let shake = self.trauma * self.trauma;
+            let mut rng = rand::thread_rng();
+            self.offset_x = (MAX_SHAKE_OFFSET * shake * rng.gen_range(-1.0..1.0)) as i32;
+            self.offset_y = (MAX_SHAKE_OFFSET * shake * rng.gen_range(-1.0..1.0)) as i32;
+        }
+        else {
+            self.offset_x = 0;
+            self.offset_y = 0;
+        }
+    }
+
+    // everything rendered between begin_render and end_render is moved by the
+    // camera offset
+    pub fn begin_render(&self, canvas: &mut Canvas<Window>) {
+        canvas.set_viewport(Rect::new(self.offset_x, self.offset_y, SCREEN_WIDTH, SCREEN_HEIGHT));
+    }
+
+    pub fn end_render(&self, canvas: &mut Canvas<Window>, options: &Options) {
+        canvas.set_viewport(None);
+        if options.flashes && self.flash_frames_left > 0 {
+            let (r, g, b) = self.flash_colour;
+            let alpha = self.flash_frames_left * FLASH_MAX_ALPHA / FLASH_FRAMES;
+            canvas.set_draw_color(Color::RGBA(r, g, b, alpha as u8));
+            canvas.fill_rect(Rect::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT)).unwrap();
+            canvas.set_draw_color(Color::RGB(0, 0, 0));
+        }
+    }
+}
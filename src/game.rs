@@ -1,31 +1,79 @@
 
-use crate::common::{SCREEN_WIDTH, SCREEN_HEIGHT};
+use crate::common::{self, SCREEN_WIDTH, SCREEN_HEIGHT, UPDATE_FPS};
 use crate::ship::Ship;
-use crate::base_bricks::BaseBricks;
-use crate::letter_bricks::LetterBricks;
+use crate::base_bricks::{self, BaseBricks};
+use crate::letter_bricks::{LetterBricks, LetterProgress};
 use crate::missile::Missile;
 use crate::mother::Mother;
 use crate::spiders::Spiders;
 use crate::bombs::Bombs;
 use crate::background::Background;
 use crate::bonus_bomb::BonusBomb;
+use crate::power_ups::{PowerUp, PowerUps};
+use crate::attacks;
 use crate::soundfx::SoundControl;
 use crate::image::Image;
 use crate::text::Text;
 use crate::animation::{AnimationSeq, Animation, Animations};
+use crate::effects::ScreenEffects;
+use crate::options::Options;
+use crate::game_mode::GameMode;
+use crate::high_scores::{HighScores, NAME_LENGTH};
+use crate::rules::Rules;
+use crate::daily::{self, Challenge, Mutators, Replay};
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay::{self, DebugOverlay};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use sdl2::controller::{Axis, Button};
 use sdl2::render::{Canvas, TextureCreator};
 use sdl2::video::{Window, WindowContext};
 use sdl2::ttf::Sdl2TtfContext;
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
+use rand::rngs::StdRng;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::array;
+use std::mem;
+#[cfg(feature = "debug-overlay")]
+use std::time::Duration;
 
 
-const SPIDER_SCORE: [u32; 3] = [40, 80, 200];
+const SPIDER_SCORE: [u32; 6] = [40, 80, 200, 150, 300, 100];
 const EXTRA_LIFE_SCORE: u32 = 6000;
+const BOMB_SCORE: u32 = 20;
+// for winning a brick back from a thief
+const RETURNED_BRICK_SCORE: u32 = 500;
+const WEAK_POINT_SCORE: u32 = 1000;
+const BOSS_SCORE: u32 = 5000;
+const PLAYER_READY_FRAMES: u32 = 150;
+const HIGH_SCORE_CYCLE: u32 = 400;
+const STICK_DEAD_ZONE: i16 = 8000;
+// enough for double missiles with rapid fire
+const MISSILES_PER_SHIP: usize = 4;
+// the defending player wins a versus game by clearing this many attacks
+const VERSUS_ATTACKS: u32 = 5;
+// time attack lasts this long
+const TIME_ATTACK_FRAMES: u32 = 3 * 60 * UPDATE_FPS;
+// shot spiders only come back for this long into each attack, after which
+// the nest runs dry so that clearing it moves on to the next attack, which
+// starts the 45 seconds over again
+const TIME_ATTACK_ATTACK_FRAMES: u32 = 45 * UPDATE_FPS;
+const WORD_PENALTY: u32 = 10000;
+const LETTERS: [&str; 3] = ["F", "I", "N"];
+// a player's controls for one frame packed into a byte, which is all that
+// a network game sends
+const CONTROL_LEFT: u8 = 1;
+const CONTROL_RIGHT: u8 = 2;
+const CONTROL_FIRE: u8 = 4;
+const CONTROL_TARGET: u8 = 8;
 
 enum State {
     Startup,
     InProgress,
+    PlayerReady(u32),
+    EnterName(usize),
     GameOver,
 }
 
@@ -33,11 +81,16 @@ impl State {
     fn playing(&self) -> bool {
         match *self {
             State::Startup => {false},
+            State::EnterName(_) => {false},
             State::GameOver => {false},
             _ => {true},
         }
     }
 
+    fn accepting_start(&self) -> bool {
+        matches!(*self, State::Startup | State::GameOver)
+    }
+
     fn screen_in_progress(&self) -> bool {
         match *self {
             State::InProgress => {true},
@@ -46,69 +99,232 @@ impl State {
     }
 }
 
+// everything that belongs to one player, kept aside while the other player
+// takes their turn in a two player game
+struct PlayerState {
+    letters: LetterProgress,
+    lives: u32,
+    score: u32,
+    screen: u32,
+    // frames played, which is the time survived in survival
+    frames: u32,
+    in_game: bool,
+}
+
+impl PlayerState {
+    fn new() -> PlayerState {
+        PlayerState {letters: LetterProgress::new(), lives: 0, score: 0, screen: 1, frames: 0, in_game: false}
+    }
+}
+
+// ship controls are kept for each player, in games with only one ship on
+// screen either player's controls will fly it, in versus the second player's
+// controls launch spiders and drop the bonus bomb
 struct GameInput {
-    left_pressed: bool,
-    right_pressed: bool,
-    fire_pressed: bool,
+    left_pressed: [bool; 2],
+    right_pressed: [bool; 2],
+    fire_pressed: [bool; 2],
+    target_pressed: bool,
     start_pressed: bool,
     pause_pressed: bool,
     inc_vol_pressed: bool,
     dec_vol_pressed: bool,
+    shake_option_pressed: bool,
+    hit_stop_option_pressed: bool,
+    flash_option_pressed: bool,
+    difficulty_option_pressed: bool,
+    shoot_bombs_option_pressed: bool,
+    mode_option_pressed: bool,
+    shared_lives_option_pressed: bool,
+    cover_option_pressed: bool,
+    rules_option_pressed: bool,
+    daily_pressed: bool,
+    typed_char: Option<char>,
+    backspace_pressed: bool,
+    return_pressed: bool,
+    // the first controller to press a button is player one's, the next player two's
+    controllers: Vec<u32>,
 }
 
 impl GameInput {
     fn new() -> GameInput {
         GameInput {
-            left_pressed: false,
-            right_pressed: false,
-            fire_pressed: false,
+            left_pressed: [false; 2],
+            right_pressed: [false; 2],
+            fire_pressed: [false; 2],
+            target_pressed: false,
             start_pressed: false,
             pause_pressed: false,
             inc_vol_pressed: false,
             dec_vol_pressed: false,
+            shake_option_pressed: false,
+            hit_stop_option_pressed: false,
+            flash_option_pressed: false,
+            difficulty_option_pressed: false,
+            shoot_bombs_option_pressed: false,
+            mode_option_pressed: false,
+            shared_lives_option_pressed: false,
+            cover_option_pressed: false,
+            rules_option_pressed: false,
+            daily_pressed: false,
+            typed_char: None,
+            backspace_pressed: false,
+            return_pressed: false,
+            controllers: Vec::with_capacity(2),
         }
     }
 
     fn reset(&mut self) {
-        self.left_pressed = false;
-        self.right_pressed = false;
-        self.fire_pressed = false;
+        self.left_pressed = [false; 2];
+        self.right_pressed = [false; 2];
+        self.fire_pressed = [false; 2];
+        self.target_pressed = false;
         self.start_pressed = false;
+        self.daily_pressed = false;
         self.pause_pressed = false;
         self.inc_vol_pressed = false;
         self.dec_vol_pressed = false;
+        self.shake_option_pressed = false;
+        self.hit_stop_option_pressed = false;
+        self.flash_option_pressed = false;
+        self.difficulty_option_pressed = false;
+        self.shoot_bombs_option_pressed = false;
+        self.mode_option_pressed = false;
+        self.shared_lives_option_pressed = false;
+        self.cover_option_pressed = false;
+        self.rules_option_pressed = false;
+        self.typed_char = None;
+        self.backspace_pressed = false;
+        self.return_pressed = false;
     }
 
     fn update_inputs(&mut self, event: &Event) {
         match event {
             Event::KeyDown {keycode: Some(keycode), ..} => {
+                // letters and numbers may be needed for entering a name
+                let key_name = keycode.name();
+                if key_name.len() == 1 {
+                    self.typed_char = key_name.chars().find(|c| c.is_ascii_alphanumeric());
+                }
                 match keycode {
-                    Keycode::Z => {self.left_pressed = true;},
-                    Keycode::X => {self.right_pressed = true;},
-                    Keycode::RShift => {self.fire_pressed = true;},
+                    Keycode::Z => {self.left_pressed[0] = true;},
+                    Keycode::X => {self.right_pressed[0] = true;},
+                    Keycode::RShift => {self.fire_pressed[0] = true;},
+                    Keycode::Left => {self.left_pressed[1] = true;},
+                    Keycode::Right => {self.right_pressed[1] = true;},
+                    Keycode::RCtrl => {self.fire_pressed[1] = true;},
+                    Keycode::Slash => {self.target_pressed = true;},
                     Keycode::Space => {self.start_pressed = true;},
                     Keycode::P => {self.pause_pressed = true;},
                     Keycode::Up => {self.inc_vol_pressed = true;},
                     Keycode::Down => {self.dec_vol_pressed = true;},
+                    Keycode::F1 => {self.shake_option_pressed = true;},
+                    Keycode::F2 => {self.hit_stop_option_pressed = true;},
+                    Keycode::F3 => {self.flash_option_pressed = true;},
+                    Keycode::F4 => {self.difficulty_option_pressed = true;},
+                    Keycode::F5 => {self.shoot_bombs_option_pressed = true;},
+                    Keycode::F6 => {self.mode_option_pressed = true;},
+                    Keycode::F7 => {self.shared_lives_option_pressed = true;},
+                    Keycode::F8 => {self.cover_option_pressed = true;},
+                    Keycode::F9 => {self.rules_option_pressed = true;},
+                    Keycode::F10 => {self.daily_pressed = true;},
+                    Keycode::Backspace => {self.backspace_pressed = true;},
+                    Keycode::Return => {self.return_pressed = true;},
                     _ => {}
                 }
             }
 
             Event::KeyUp {keycode: Some(keycode), ..} => {
                 match keycode {
-                    Keycode::Z => {self.left_pressed = false;},
-                    Keycode::X => {self.right_pressed = false;},
+                    Keycode::Z => {self.left_pressed[0] = false;},
+                    Keycode::X => {self.right_pressed[0] = false;},
+                    Keycode::Left => {self.left_pressed[1] = false;},
+                    Keycode::Right => {self.right_pressed[1] = false;},
                     Keycode::Space => {self.start_pressed = false;},
                     _ => {}
                 }
             },
 
+            Event::ControllerButtonDown {which, button, ..} => {
+                if let Some(player) = self.controller_player(*which) {
+                    match button {
+                        Button::DPadLeft => {self.left_pressed[player] = true;},
+                        Button::DPadRight => {self.right_pressed[player] = true;},
+                        Button::A => {self.fire_pressed[player] = true;},
+                        Button::B => {self.target_pressed = true;},
+                        Button::Start => {self.start_pressed = true;},
+                        _ => {}
+                    }
+                }
+            },
+
+            Event::ControllerButtonUp {which, button, ..} => {
+                if let Some(player) = self.controller_player(*which) {
+                    match button {
+                        Button::DPadLeft => {self.left_pressed[player] = false;},
+                        Button::DPadRight => {self.right_pressed[player] = false;},
+                        Button::Start => {self.start_pressed = false;},
+                        _ => {}
+                    }
+                }
+            },
+
+            Event::ControllerAxisMotion {which, axis: Axis::LeftX, value, ..} => {
+                if let Some(player) = self.controller_player(*which) {
+                    self.left_pressed[player] = *value < - STICK_DEAD_ZONE;
+                    self.right_pressed[player] = *value > STICK_DEAD_ZONE;
+                }
+            },
+
             _ => {},
         }
     }
 
-    fn acknowledge_fire(&mut self) {
-        self.fire_pressed = false;
+    fn controller_player(&mut self, which: u32) -> Option<usize> {
+        match self.controllers.iter().position(|&c| c == which) {
+            Some(player) => {Some(player)},
+            None if self.controllers.len() < 2 => {
+                self.controllers.push(which);
+                Some(self.controllers.len() - 1)
+            },
+            None => {None},
+        }
+    }
+
+    // the controls that fly the given ship
+    fn ship_controls(&self, ship: usize, game_mode: GameMode) -> (bool, bool, bool) {
+        if ! game_mode.controls_shared() {
+            (self.left_pressed[ship], self.right_pressed[ship], self.fire_pressed[ship])
+        }
+        else {
+            (self.left_pressed.iter().any(|&p| p), self.right_pressed.iter().any(|&p| p),
+             self.fire_pressed.iter().any(|&p| p))
+        }
+    }
+
+    fn acknowledge_fire(&mut self, ship: usize, game_mode: GameMode) {
+        if ! game_mode.controls_shared() {
+            self.fire_pressed[ship] = false;
+        }
+        else {
+            self.fire_pressed = [false; 2];
+        }
+    }
+
+    // the local player's controls for a network game, from either set of keys
+    fn take_controls(&mut self) -> u8 {
+        let mut controls = 0;
+        if self.left_pressed.iter().any(|&p| p) {controls |= CONTROL_LEFT;}
+        if self.right_pressed.iter().any(|&p| p) {controls |= CONTROL_RIGHT;}
+        if self.fire_pressed.iter().any(|&p| p) {controls |= CONTROL_FIRE;}
+        if self.target_pressed {controls |= CONTROL_TARGET;}
+        self.fire_pressed = [false; 2];
+        self.target_pressed = false;
+        controls
+    }
+
+    fn acknowledge_target(&mut self) {
+        self.target_pressed = false;
     }
 
     fn acknowledge_pause(&mut self) {
@@ -119,18 +335,42 @@ impl GameInput {
         self.inc_vol_pressed = false;
         self.dec_vol_pressed = false;
     }
+
+    fn acknowledge_option_change(&mut self) {
+        self.shake_option_pressed = false;
+        self.hit_stop_option_pressed = false;
+        self.flash_option_pressed = false;
+        self.difficulty_option_pressed = false;
+        self.shoot_bombs_option_pressed = false;
+        self.mode_option_pressed = false;
+        self.shared_lives_option_pressed = false;
+        self.cover_option_pressed = false;
+        self.rules_option_pressed = false;
+    }
+
+    fn acknowledge_daily(&mut self) {
+        self.daily_pressed = false;
+    }
+
+    fn acknowledge_text_entry(&mut self) {
+        self.typed_char = None;
+        self.backspace_pressed = false;
+        self.return_pressed = false;
+    }
 }
 
 pub struct Game<'a, 'b> {
     game_state: State,
-    ship: Ship<'a>,
-    missile: Missile<'a>,
-    base_bricks: BaseBricks<'a>,
+    ships: [Ship<'a>; 2],
+    missiles: [[Missile<'a>; MISSILES_PER_SHIP]; 2],
+    base_bricks: BaseBricks,
+    brick_image: Image<'a>,
     letter_bricks: LetterBricks<'a>,
     mother: Mother<'a>,
     spiders: Spiders<'a>,
     bombs: Bombs<'a>,
     bonus_bomb: BonusBomb<'a>,
+    power_ups: PowerUps<'a>,
     game_over_image: Image<'a>,
     instructions_image: Image<'a>,
     screen_flag_image: Image<'a>,
@@ -138,26 +378,65 @@ pub struct Game<'a, 'b> {
     frame_count: u32,
     score: u32,
     screen: u32,
+    play_frames: u32,
     sound_control: SoundControl,
     paused: bool,
     animations: Animations<'a, 'b>,
     background: Background<'a>,
     text32: Text<'a, 'b>,
+    text24: Text<'a, 'b>,
+    options: Options,
+    effects: ScreenEffects,
+    game_mode: GameMode,
+    rules: Rules,
+    current_player: usize,
+    players: [PlayerState; 2],
+    high_scores: HighScores,
+    coop_high_scores: HighScores,
+    survival_high_scores: HighScores,
+    time_attack_high_scores: HighScores,
+    pending_names: Vec<usize>,
+    name_entry: String,
+    // versus only, the letter the mother player wants built and who won
+    target_letter: Option<usize>,
+    winner: Option<usize>,
+    rng: StdRng,
+    // in a network game, which player is on this machine, their own options
+    // put aside while the host's are used, and the controls for this frame
+    network_player: Option<usize>,
+    local_options: Option<Options>,
+    network_controls: [u8; 2],
+    network_status: Option<&'static str>,
+    // a spectator only shows what it is told, see spectator.rs
+    spectating: bool,
+    // the daily challenge being played or replayed, its controls and how the
+    // last one went, see daily.rs
+    challenge: Option<Challenge>,
+    mutators: Mutators,
+    replay: Replay,
+    replaying: bool,
+    daily_standing: Option<String>,
+    #[cfg(feature = "debug-overlay")]
+    debug_overlay: DebugOverlay<'a, 'b>,
 }
 
 impl<'a, 'b> Game<'a, 'b> {
     pub fn new(texture_creator: &'a TextureCreator<WindowContext>,
         ttf_context: &'a Sdl2TtfContext) -> Game<'a, 'b> {
-        Game {
+        let options = Options::load();
+        let mut game = Game {
             game_state: State::Startup,
-            ship: Ship::new(texture_creator),
-            missile: Missile::new(texture_creator),
-            base_bricks: BaseBricks::new(texture_creator),
+            ships: [Ship::new(texture_creator, 0, options.ship_collision),
+                    Ship::new(texture_creator, 1, options.ship_collision)],
+            missiles: array::from_fn(|_| array::from_fn(|_| Missile::new(texture_creator))),
+            base_bricks: BaseBricks::new(),
+            brick_image: base_bricks::brick_image(texture_creator),
             letter_bricks: LetterBricks::new(texture_creator),
             mother: Mother::new(texture_creator),
-            spiders: Spiders::new(texture_creator),
-            bombs: Bombs::new(texture_creator),
-            bonus_bomb: BonusBomb::new(texture_creator),
+            spiders: Spiders::new(texture_creator, options.spider_collision),
+            bombs: Bombs::new(texture_creator, options.ship_collision),
+            bonus_bomb: BonusBomb::new(texture_creator, options.bonus_bomb_collision),
+            power_ups: PowerUps::new(texture_creator),
             game_over_image: Image::new(texture_creator, "game_over.png", 427, 72),
             instructions_image: Image::new(texture_creator, "instructions.png", 346, 300),
             screen_flag_image: Image::new(texture_creator, "screen_flag.png", 14, 20),
@@ -165,11 +444,53 @@ impl<'a, 'b> Game<'a, 'b> {
             frame_count: 0,
             score: 0,
             screen: 0,
+            play_frames: 0,
             sound_control: SoundControl::new(),
             paused: false,
             animations: Animations::new(texture_creator, ttf_context),
             background: Background::new(texture_creator),
             text32: Text::new(ttf_context, 32),
+            text24: Text::new(ttf_context, 24),
+            options,
+            effects: ScreenEffects::new(),
+            game_mode: GameMode::Single,
+            rules: Rules::Classic,
+            current_player: 0,
+            players: [PlayerState::new(), PlayerState::new()],
+            high_scores: HighScores::load("high_scores.txt"),
+            coop_high_scores: HighScores::load("coop_high_scores.txt"),
+            survival_high_scores: HighScores::load_timed("survival_high_scores.txt"),
+            time_attack_high_scores: HighScores::load("time_attack_high_scores.txt"),
+            pending_names: Vec::with_capacity(2),
+            name_entry: String::with_capacity(NAME_LENGTH),
+            target_letter: None,
+            winner: None,
+            rng: common::new_random(rand::random()),
+            network_player: None,
+            local_options: None,
+            network_controls: [0; 2],
+            network_status: None,
+            spectating: false,
+            challenge: None,
+            mutators: Mutators::none(),
+            replay: Replay::new(),
+            replaying: false,
+            daily_standing: None,
+            #[cfg(feature = "debug-overlay")]
+            debug_overlay: DebugOverlay::new(ttf_context),
+        };
+        game.apply_options();
+        game.game_mode = game.options.game_mode;
+        game
+    }
+
+    fn apply_options(&mut self) {
+        self.spiders.set_collision_mode(self.options.spider_collision);
+        self.spiders.set_evasion(self.options.difficulty.evasion());
+        self.bonus_bomb.set_collision_mode(self.options.bonus_bomb_collision);
+        self.bombs.set_collision_mode(self.options.ship_collision);
+        for ship in &mut self.ships {
+            ship.set_collision_mode(self.options.ship_collision);
         }
     }
 
@@ -177,38 +498,672 @@ impl<'a, 'b> Game<'a, 'b> {
         self.game_state = State::InProgress;
         self.mother.full_reset();
         self.screen = 1;
+        // versus is always played to the classic rules
+        self.rules = if self.options.game_mode == GameMode::Versus {Rules::Classic} else {self.options.rules};
+        self.spiders.set_escalating(self.rules.escalating());
+        self.spiders.set_fast(self.mutators.fast_spiders);
+        self.spiders.set_double_bombs(self.mutators.double_bombs);
         self.spiders.reset(self.screen);
-        self.ship.reset();
-        self.missile.reset();
+        self.base_bricks.set_cover(self.options.brick_cover);
         self.base_bricks.reset();
         self.base_bricks.update();
         self.letter_bricks.reset();
         self.bombs.reset();
         self.bonus_bomb.reset();
+        self.power_ups.reset();
         self.score = 0;
         self.frame_count = 0;
+        self.play_frames = 0;
         self.game_input.reset();
+        self.effects.reset();
         self.spiders.turn_sound_on();
         self.bonus_bomb.turn_sound_on();
-        self.ship.proceed_with_changeover();
+        self.mother.turn_sound_on();
+        self.game_mode = self.options.game_mode;
+        self.mother.set_controlled(self.game_mode == GameMode::Versus);
+        self.network_status = None;
+        self.daily_standing = None;
+        self.target_letter = None;
+        self.winner = None;
+        if self.game_mode.ships() > 1 {
+            self.ships[0].set_home(1.0 / 3.0);
+            self.ships[1].set_home(2.0 / 3.0);
+        }
+        else {
+            self.ships[0].set_home(0.5);
+        }
+        for p in 0..2 {
+            self.ships[p].reset();
+            for missile in &mut self.missiles[p] {
+                missile.reset();
+            }
+        }
+        if self.shared_lives() {
+            // the first ship holds the pool of lives for both
+            let lives = self.ships[0].lives() + self.ships[1].lives();
+            self.ships[0].set_lives(lives);
+            self.ships[1].set_lives(0);
+        }
+        if self.mutators.one_life {
+            self.ships[0].set_lives(1);
+        }
+        self.current_player = 0;
+        self.players = [PlayerState::new(), PlayerState::new()];
+        for p in 0..self.game_mode.players() {
+            self.players[p].lives = self.ships[0].lives();
+            self.players[p].in_game = true;
+        }
+        for p in 0..self.game_mode.ships() {
+            self.ship_changeover(p);
+        }
         self.screen_start();
     }
 
+    // both games are started from the same seed with the host's options, so
+    // from here on only their controls need to be exchanged
+    pub fn start_network_game(&mut self, seed: u64, options: &str, player: usize) {
+        let mut options = Options::from_contents(options);
+        if options.game_mode != GameMode::Versus {
+            options.game_mode = GameMode::Cooperative;
+        }
+        self.local_options = Some(mem::replace(&mut self.options, options));
+        self.apply_options();
+        self.network_player = Some(player);
+        self.network_controls = [0; 2];
+        self.network_status = None;
+        self.rng = common::new_random(seed);
+        common::swap_random(&mut self.rng);
+        self.new_game();
+        common::swap_random(&mut self.rng);
+    }
+
+    // the player's own options are put aside for the defaults, apart from
+    // the effects which make no difference to how the game plays out. The
+    // game's generator is swapped in while it updates, so the challenge's
+    // takes its place there
+    fn start_daily(&mut self, challenge: Challenge, replay: Option<Replay>) {
+        let mut options = Options::new();
+        options.screen_shake = self.options.screen_shake;
+        options.hit_stop = self.options.hit_stop;
+        options.flashes = self.options.flashes;
+        self.local_options = Some(mem::replace(&mut self.options, options));
+        self.apply_options();
+        self.mutators = challenge.mutators;
+        self.replaying = replay.is_some();
+        self.replay = replay.unwrap_or_else(Replay::new);
+        common::swap_random(&mut common::new_random(challenge.seed));
+        self.challenge = Some(challenge);
+        self.new_game();
+    }
+
+    pub fn start_replay(&mut self, challenge: Challenge, replay: Replay) {
+        common::swap_random(&mut self.rng);
+        self.start_daily(challenge, Some(replay));
+        common::swap_random(&mut self.rng);
+    }
+
+    // a result is kept unless it was a replay, and the player's own options
+    // come back
+    fn end_daily(&mut self) {
+        if let Some(challenge) = self.challenge.take() {
+            let standing = if self.replaying {
+                format!("Replay of {}", challenge.date)
+            }
+            else {
+                daily::save_result(&challenge, self.score, self.play_frames / UPDATE_FPS, &self.replay);
+                let results = daily::load_results(&challenge.date);
+                // the results can't be read back if they couldn't be saved
+                match results.first() {
+                    Some(best) => {
+                        let place = results.iter().position(|r| r.score <= self.score).unwrap_or(0) + 1;
+                        format!("Daily {}, {} of {}, best {}", challenge.date, place, results.len(), best.score)
+                    },
+                    None => {format!("Daily {}, score {}", challenge.date, self.score)},
+                }
+            };
+            self.daily_standing = Some(standing);
+        }
+        if let Some(options) = self.local_options.take() {
+            self.options = options;
+            self.apply_options();
+        }
+        self.mutators = Mutators::none();
+        self.replay = Replay::new();
+        self.replaying = false;
+    }
+
+    // the game carries on locally if it is over, otherwise it is abandoned
+    pub fn end_network_game(&mut self, status: Option<&'static str>) {
+        if let Some(options) = self.local_options.take() {
+            self.options = options;
+            self.apply_options();
+        }
+        self.network_player = None;
+        self.network_status = status;
+        if self.game_state.playing() {
+            self.spiders.turn_sound_off();
+            self.bonus_bomb.turn_sound_off();
+            self.mother.turn_sound_off();
+            self.game_state = State::GameOver;
+        }
+    }
+
+    pub fn network_game_over(&self) -> bool {
+        ! self.game_state.playing()
+    }
+
+    pub fn set_network_status(&mut self, status: Option<&'static str>) {
+        self.network_status = status;
+    }
+
+    pub fn options_contents(&self) -> String {
+        self.options.contents()
+    }
+
+    pub fn take_local_controls(&mut self) -> u8 {
+        self.game_input.take_controls()
+    }
+
+    pub fn set_network_controls(&mut self, controls: [u8; 2]) {
+        self.network_controls = controls;
+    }
+
+    // everything that decides how the game plays out from here, networked
+    // games compare these to check they are still in step
+    pub fn state_hash(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        (self.score, self.screen, self.frame_count, self.current_player).hash(&mut state);
+        mem::discriminant(&self.game_state).hash(&mut state);
+        for p in 0..self.game_mode.ships() {
+            self.ships[p].hash_state(&mut state);
+            for missile in &self.missiles[p] {
+                missile.hash_state(&mut state);
+            }
+        }
+        self.base_bricks.hash_state(&mut state);
+        self.letter_bricks.hash_state(&mut state);
+        self.mother.hash_state(&mut state);
+        self.spiders.hash_state(&mut state);
+        self.bombs.hash_state(&mut state);
+        self.bonus_bomb.hash_state(&mut state);
+        self.power_ups.hash_state(&mut state);
+        state.finish()
+    }
+
+    pub fn start_spectating(&mut self) {
+        self.spectating = true;
+        self.network_status = Some("Waiting for the game");
+    }
+
+    pub fn describe(&self) -> String {
+        let state = match self.game_state {
+            State::Startup => {"S"},
+            State::InProgress => {"P"},
+            State::PlayerReady(_) => {"R"},
+            State::EnterName(_) => {"E"},
+            State::GameOver => {"G"},
+        };
+        let ships: Vec<String> = self.ships.iter().take(self.game_mode.ships())
+            .map(|ship| ship.describe())
+            .collect();
+        let missiles: Vec<String> = self.missiles.iter().take(self.game_mode.ships()).flatten()
+            .map(|missile| missile.describe())
+            .collect();
+        format!("f={} mode={} st={} score={} screen={} ships={} missiles={} mother={} spiders={} \
+            bombs={} bonus={} power={} base={} letters={}",
+            self.frame_count, self.game_mode.name().replace(' ', "_"), state, self.score,
+            self.screen, ships.join(";"), missiles.join(";"), self.mother.describe(),
+            self.spiders.describe(), self.bombs.describe(), self.bonus_bomb.describe(),
+            self.power_ups.describe(), self.base_bricks.describe(), self.letter_bricks.describe())
+    }
+
+    pub fn watch(&mut self, line: &str) {
+        self.network_status = None;
+        for (key, value) in line.split(' ').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "f" => {self.frame_count = value.parse().unwrap_or(self.frame_count);},
+                "mode" => {
+                    self.game_mode = GameMode::from_name(&value.replace('_', " ")).unwrap_or(self.game_mode);
+                },
+                "st" => {
+                    self.game_state = match value {
+                        "P" => {State::InProgress},
+                        "R" => {State::PlayerReady(0)},
+                        "E" => {State::EnterName(0)},
+                        "G" => {State::GameOver},
+                        _ => {State::Startup},
+                    };
+                },
+                "score" => {self.score = value.parse().unwrap_or(self.score);},
+                "screen" => {
+                    self.screen = value.parse().unwrap_or(self.screen);
+                    let attack = attacks::attack(self.screen);
+                    self.base_bricks.set_layout(attack.base_layout, attack.resupply);
+                },
+                "ships" => {
+                    for (ship, d) in self.ships.iter_mut().zip(value.split(';')) {
+                        ship.watch(d);
+                    }
+                },
+                "missiles" => {
+                    for (missile, d) in self.missiles.iter_mut().flatten().zip(value.split(';')) {
+                        missile.watch(d);
+                    }
+                },
+                "mother" => {self.mother.watch(value);},
+                "spiders" => {self.spiders.watch(value);},
+                "bombs" => {self.bombs.watch(value);},
+                "bonus" => {self.bonus_bomb.watch(value);},
+                "power" => {self.power_ups.watch(value);},
+                "base" => {self.base_bricks.watch(value);},
+                "letters" => {self.letter_bricks.watch(value);},
+                _ => {},
+            }
+        }
+    }
+
+    // in a network game or the daily challenge the controls for each frame
+    // are packed up first, to be exchanged or recorded
+    fn packed_controls(&self) -> bool {
+        self.network_player.is_some() || self.challenge.is_some()
+    }
+
+    // then the controls come from those packed up rather than straight from
+    // the keyboard
+    fn ship_controls(&self, p: usize) -> (bool, bool, bool) {
+        if self.packed_controls() {
+            let controls = self.network_controls[p];
+            (controls & CONTROL_LEFT != 0, controls & CONTROL_RIGHT != 0, controls & CONTROL_FIRE != 0)
+        }
+        else {
+            self.game_input.ship_controls(p, self.game_mode)
+        }
+    }
+
+    fn acknowledge_fire(&mut self, p: usize) {
+        if ! self.packed_controls() {
+            self.game_input.acknowledge_fire(p, self.game_mode);
+        }
+    }
+
+    fn target_control(&mut self) -> bool {
+        match self.network_player {
+            Some(_) => {self.network_controls[1] & CONTROL_TARGET != 0},
+            None => {
+                let pressed = self.game_input.target_pressed;
+                self.game_input.acknowledge_target();
+                pressed
+            },
+        }
+    }
+
+    fn missiles_flying(&self, p: usize) -> bool {
+        self.missiles[p].iter().any(|missile| missile.flying())
+    }
+
+    fn shared_lives(&self) -> bool {
+        self.game_mode == GameMode::Cooperative && self.options.shared_lives
+    }
+
+    // a co-op ship with shared lives takes one from the pool first
+    fn ship_changeover(&mut self, p: usize) {
+        if p > 0 && self.shared_lives() && self.ships[0].lives() > 0 {
+            let pool = self.ships[0].lives();
+            self.ships[0].set_lives(pool - 1);
+            let lives = self.ships[p].lives();
+            self.ships[p].set_lives(lives + 1);
+        }
+        self.ships[p].proceed_with_changeover();
+    }
+
+    fn ship_life_left(&self, p: usize) -> bool {
+        self.ships[p].life_left() || (p > 0 && self.shared_lives() && self.ships[0].lives() > 0)
+    }
+
+    // in co-op the game carries on while a ship waits to come back, so it
+    // only needs its own missile down and a clear path home
+    fn coop_changeover(&mut self) {
+        for p in 0..2 {
+            let (x_min, x_max) = self.ships[p].home_span();
+            if self.players[p].in_game &&
+                self.ships[p].waiting_for_changeover() &&
+                self.ships[p].enough_delay_for_changeover() &&
+                self.spiders.clear() &&
+                ! self.bombs.in_flight_between(x_min, x_max) &&
+                ! self.missiles_flying(p) {
+                self.ship_changeover(p);
+            }
+        }
+    }
+
+    fn other_player_in_game(&self) -> bool {
+        self.game_mode == GameMode::Alternating && self.players[1 - self.current_player].in_game
+    }
+
+    fn store_player(&mut self) {
+        let player = &mut self.players[self.current_player];
+        player.letters = self.letter_bricks.progress();
+        player.lives = self.ships[0].lives();
+        player.score = self.score;
+        player.screen = self.screen;
+        player.frames = self.play_frames;
+    }
+
+    fn switch_player(&mut self) {
+        self.store_player();
+        self.current_player = 1 - self.current_player;
+        let player = &self.players[self.current_player];
+        self.letter_bricks.restore_progress(&player.letters);
+        self.ships[0].set_lives(player.lives);
+        self.score = player.score;
+        self.screen = player.screen;
+        self.play_frames = player.frames;
+        // the incoming player starts their current attack afresh
+        self.mother.reset(self.screen);
+        self.spiders.reset(self.screen);
+        self.bombs.reset();
+        self.bonus_bomb.reset();
+        self.power_ups.reset();
+        for missile in &mut self.missiles[0] {
+            missile.reset();
+        }
+        self.base_bricks.untarget_all();
+        self.frame_count = 0;
+        self.game_state = State::PlayerReady(PLAYER_READY_FRAMES);
+    }
+
+    fn update_player_ready(&mut self) {
+        if let State::PlayerReady(n) = self.game_state {
+            if n > 0 {
+                self.game_state = State::PlayerReady(n - 1);
+            }
+            else {
+                self.game_state = State::InProgress;
+                self.ships[0].proceed_with_changeover();
+                self.screen_start();
+            }
+        }
+    }
+
+    // in survival the word never ends the game, finishing it costs a life
+    // and it has to be built all over again
+    fn word_complete_in_survival(&mut self) {
+        self.letter_bricks.clear();
+        self.effects.flash(255, 255, 255);
+        self.effects.add_trauma(0.6);
+        // in co-op it comes from the ship with the most lives to spare
+        let p = (0..self.game_mode.ships())
+            .filter(|&p| self.players[p].in_game || self.game_mode != GameMode::Cooperative)
+            .max_by_key(|&p| self.ships[p].lives())
+            .unwrap_or(0);
+        let lives = self.ships[p].lives();
+        if lives > 0 {
+            self.ships[p].set_lives(lives - 1);
+        }
+        else if self.ships[p].alive() {
+            self.ships[p].kill(&mut self.animations, &mut self.effects);
+        }
+    }
+
+    // in time attack finishing the word only costs points, and it has to be
+    // built all over again
+    fn word_complete_in_time_attack(&mut self) {
+        self.letter_bricks.clear();
+        self.effects.flash(255, 255, 255);
+        self.effects.add_trauma(0.6);
+        self.score = self.score.saturating_sub(WORD_PENALTY);
+    }
+
+    // the clock running out puts a player out as a finished word does in
+    // the classic rules
+    fn time_up(&mut self) {
+        if self.other_player_in_game() {
+            if self.players[self.current_player].in_game {
+                self.players[self.current_player].in_game = false;
+                self.ships[0].stand_down();
+            }
+        }
+        else {
+            self.game_over();
+        }
+    }
+
+    // a player is out when their word is complete or they have no lives
+    // left, the game is over when every player is out
+    fn check_players_out(&mut self) {
+        if self.game_mode == GameMode::Cooperative {
+            // both ships play on the one word, the game is over when it is
+            // complete or neither ship has a life left
+            for p in 0..2 {
+                if ! self.ship_life_left(p) {
+                    self.players[p].in_game = false;
+                }
+            }
+            if self.letter_bricks.complete() || ! self.players.iter().any(|p| p.in_game) {
+                if self.letter_bricks.complete() {
+                    self.letter_bricks.initiate_expansion();
+                }
+                self.game_over();
+            }
+            return;
+        }
+        if self.letter_bricks.complete() || ! self.ships[0].life_left() {
+            if self.other_player_in_game() {
+                if self.players[self.current_player].in_game {
+                    self.players[self.current_player].in_game = false;
+                    if self.letter_bricks.complete() {
+                        self.letter_bricks.initiate_expansion();
+                        self.ships[0].stand_down();
+                    }
+                }
+            }
+            else {
+                if self.letter_bricks.complete() {
+                    self.letter_bricks.initiate_expansion();
+                }
+                if self.game_mode == GameMode::Versus {
+                    self.winner = Some(1);
+                }
+                self.game_over();
+            }
+        }
+    }
+
+    fn game_over(&mut self) {
+        self.store_player();
+        self.players[self.current_player].in_game = false;
+        self.spiders.turn_sound_off();
+        self.bonus_bomb.turn_sound_off();
+        self.mother.turn_sound_off();
+        // the daily challenge has its own results rather than high scores
+        if self.challenge.is_some() {
+            self.end_daily();
+            self.pending_names.clear();
+            self.next_name_entry();
+            return;
+        }
+        // a co-op score belongs to the team so only one name is entered, a
+        // versus game is about who wins rather than the score
+        let names = match self.game_mode {
+            GameMode::Cooperative => {1},
+            GameMode::Versus => {0},
+            _ => {self.game_mode.players()},
+        };
+        self.pending_names = (0..names)
+            .filter(|&p| self.high_score_table(self.game_mode, self.rules).qualifies(self.players[p].score))
+            .collect();
+        self.next_name_entry();
+    }
+
+    // co-op scores are a team effort so they have a table of their own, as
+    // do survival games, which also keep how long they lasted, and time
+    // attack
+    fn high_score_table(&self, game_mode: GameMode, rules: Rules) -> &HighScores {
+        match (game_mode, rules) {
+            (_, Rules::Survival) => {&self.survival_high_scores},
+            (_, Rules::TimeAttack) => {&self.time_attack_high_scores},
+            (GameMode::Cooperative, _) => {&self.coop_high_scores},
+            _ => {&self.high_scores},
+        }
+    }
+
+    fn high_score_table_mut(&mut self, game_mode: GameMode, rules: Rules) -> &mut HighScores {
+        match (game_mode, rules) {
+            (_, Rules::Survival) => {&mut self.survival_high_scores},
+            (_, Rules::TimeAttack) => {&mut self.time_attack_high_scores},
+            (GameMode::Cooperative, _) => {&mut self.coop_high_scores},
+            _ => {&mut self.high_scores},
+        }
+    }
+
+    fn next_name_entry(&mut self) {
+        self.name_entry.clear();
+        if self.pending_names.is_empty() {
+            self.game_state = State::GameOver;
+        }
+        else {
+            self.game_state = State::EnterName(self.pending_names.remove(0));
+        }
+    }
+
+    fn update_name_entry(&mut self) {
+        if let State::EnterName(player) = self.game_state {
+            if let Some(c) = self.game_input.typed_char {
+                if self.name_entry.len() < NAME_LENGTH {
+                    self.name_entry.push(c);
+                }
+            }
+            if self.game_input.backspace_pressed {
+                self.name_entry.pop();
+            }
+            if self.game_input.return_pressed && ! self.name_entry.is_empty() {
+                let score = self.players[player].score;
+                let seconds = self.players[player].frames / UPDATE_FPS;
+                let name = self.name_entry.clone();
+                self.high_score_table_mut(self.game_mode, self.rules).insert(&name, score, seconds);
+                self.next_name_entry();
+            }
+        }
+        self.game_input.acknowledge_text_entry();
+    }
+
     fn increase_score(&mut self, inc: u32) {
         let q = self.score / EXTRA_LIFE_SCORE;
         self.score += inc;
-        if (self.score / EXTRA_LIFE_SCORE) > q {
-            self.ship.award_extra_life(&mut self.animations);
+        if (self.score / EXTRA_LIFE_SCORE) > q && ! self.mutators.no_extra_lives {
+            // in co-op the extra life goes to the ship that needs it most
+            let p = if self.game_mode == GameMode::Cooperative && ! self.shared_lives() &&
+                self.players[1].in_game &&
+                (! self.players[0].in_game || self.ships[1].lives() < self.ships[0].lives())
+                {1} else {0};
+            self.ships[p].award_extra_life(&mut self.animations);
         }
     }
 
     fn render_score(&self, canvas: &mut Canvas<Window>) {
-        self.text32.render(canvas, SCREEN_WIDTH as i32 / 2 - 60, 5,
-            79, 120, 181, 255, &format!("{:07}", self.score));
+        if self.game_mode == GameMode::Alternating {
+            // the player not currently up is shown dimmed
+            for p in 0..2 {
+                let (score, a) = if p == self.current_player
+                    {(self.score, 255)} else {(self.players[p].score, 100)};
+                let x = if p == 0 {10} else {SCREEN_WIDTH as i32 - 180};
+                self.text32.render(canvas, x, 5, 79, 120, 181, a,
+                    &format!("{}UP {:07}", p + 1, score));
+            }
+        }
+        else {
+            self.text32.render(canvas, SCREEN_WIDTH as i32 / 2 - 60, 5,
+                79, 120, 181, 255, &format!("{:07}", self.score));
+        }
+    }
+
+    fn render_high_scores(&self, canvas: &mut Canvas<Window>) {
+        let (x, title) = match (self.options.game_mode, self.options.rules) {
+            (GameMode::Versus, _) => {(215, "High Scores")},
+            (_, Rules::Survival) => {(165, "Survival High Scores")},
+            (_, Rules::TimeAttack) => {(140, "Time Attack High Scores")},
+            (GameMode::Cooperative, _) => {(180, "Co-op High Scores")},
+            _ => {(215, "High Scores")},
+        };
+        self.text32.render(canvas, x, 320, 79, 120, 181, 255, title);
+        let rules = if self.options.game_mode == GameMode::Versus {Rules::Classic} else {self.options.rules};
+        let table = self.high_score_table(self.options.game_mode, rules);
+        for (i, entry) in table.entries().iter().enumerate() {
+            let time = if table.timed() {format!(" {}", common::format_time(entry.seconds))} else {String::new()};
+            self.text24.render(canvas, 190, 360 + 27 * i as i32, 79, 120, 181, 255,
+                &format!("{:2}. {:<3} {:07}{}", i + 1, entry.name, entry.score, time));
+        }
+    }
+
+    fn render_name_entry(&self, canvas: &mut Canvas<Window>, player: usize) {
+        let who = match self.game_mode {
+            GameMode::Single | GameMode::Versus => {String::new()},
+            GameMode::Alternating => {format!("Player {} - ", player + 1)},
+            GameMode::Cooperative => {String::from("Team - ")},
+        };
+        self.text32.render(canvas, 140, 360, 79, 120, 181, 255,
+            &format!("{}New high score!", who));
+        let cursor = if self.name_entry.len() < NAME_LENGTH && (self.frame_count / 20).is_multiple_of(2)
+            {"_"} else {""};
+        self.text32.render(canvas, 140, 410, 79, 120, 181, 255,
+            &format!("Enter initials: {}{}", self.name_entry, cursor));
+    }
+
+    // the second player steers the mother in versus
+    fn update_mother_control(&mut self) {
+        let (left, right, fire) = self.ship_controls(1);
+        let launch = if left {Some(-1)} else if right {Some(1)} else {None};
+        self.mother.request_launch(launch);
+        if fire {
+            self.mother.request_bonus_bomb();
+            self.acknowledge_fire(1);
+        }
+        if self.target_control() {
+            self.target_letter = match self.target_letter {
+                None => {Some(0)},
+                Some(l) if l < LETTERS.len() - 1 => {Some(l + 1)},
+                Some(_) => {None},
+            };
+            self.letter_bricks.set_preferred_letter(self.target_letter);
+        }
+    }
+
+    fn render_challenge(&self, canvas: &mut Canvas<Window>) {
+        if let Some(ref challenge) = self.challenge {
+            let what = if self.replaying {"Replay"} else {"Daily"};
+            self.text24.render(canvas, 10, 45, 79, 120, 181, 255,
+                &format!("{} {}: {}", what, challenge.date, challenge.mutators.names().join(", ")));
+        }
+    }
+
+    // how long has been survived or is left on the clock
+    fn render_clock(&self, canvas: &mut Canvas<Window>) {
+        let clock = match self.rules {
+            Rules::Survival => {format!("Survived {}", common::format_time(self.play_frames / UPDATE_FPS))},
+            Rules::TimeAttack => {
+                let frames_left = TIME_ATTACK_FRAMES.saturating_sub(self.play_frames);
+                format!("Time left {}", common::format_time(frames_left.div_ceil(UPDATE_FPS)))
+            },
+            Rules::Classic => {return;},
+        };
+        self.text24.render(canvas, 10, 45, 79, 120, 181, 255, &clock);
+    }
+
+    fn render_versus(&self, canvas: &mut Canvas<Window>) {
+        let target = self.target_letter.map_or("any", |l| LETTERS[l]);
+        self.text24.render(canvas, 10, 45, 79, 120, 181, 255,
+            &format!("Attack {}/{}, target {}", self.screen, VERSUS_ATTACKS, target));
     }
 
     fn screen_start(&mut self) {
         let screen_number = self.screen;
+        let attack = attacks::attack(screen_number);
+        self.bonus_bomb.set_weights(attack.bonus_weights);
+        self.letter_bricks.set_targeting(attack.letter_targeting);
+        self.base_bricks.set_targeting(attack.base_targeting);
+        self.base_bricks.set_layout(attack.base_layout, attack.resupply);
+        let escalation = if self.rules.escalating() {attacks::escalation(screen_number)} else {0};
+        self.bombs.set_limit(attack.bombs, escalation);
         let animation = Animation::new(AnimationSeq::ScreenStart(screen_number));
         self.animations.register(animation);
     }
@@ -219,103 +1174,348 @@ impl<'a, 'b> Game<'a, 'b> {
         }
     }
 
-    // a collision means occupying the same space in the same frame
-    // that may turn out to be too naive but it will do for now
+    // a collision means the paths of the two objects crossed at some point
+    // during the last frame, see ScreenObjectArea::collides
     fn missile_collision(&mut self) {
-        if self.missile.flying() {
-            if let Some(spider_id) = self.spiders.collision(self.missile.area()) {
+        for p in 0..self.game_mode.ships() {
+            for m in 0..MISSILES_PER_SHIP {
+                self.ship_missile_collision(p, m);
+            }
+        }
+    }
+
+    fn ship_missile_collision(&mut self, p: usize, m: usize) {
+        if self.missiles[p][m].flying() {
+            if let Some(spider_id) = self.spiders.collision(&self.missiles[p][m].hitbox()) {
+                self.missiles[p][m].hit_spider();
                 let target_brick_id = self.spiders.target_brick_id(spider_id);
-                if let Some(brick_id) = target_brick_id {
+                if self.spiders.hit(spider_id) {
+                    if let Some(brick_id) = target_brick_id {
+                        if self.spiders.targets_letter(spider_id) {
+                            self.letter_bricks.untarget(brick_id);
+                        }
+                        else {
+                            self.base_bricks.untarget(brick_id);
+                        }
+                    }
+                    let points = SPIDER_SCORE[self.spiders.spider_type(spider_id)];
+                    self.increase_score(points);
                     if self.spiders.carrying(spider_id) {
-                        self.letter_bricks.untarget(brick_id);
+                        self.increase_score(points);
                     }
-                    else {
-                        self.base_bricks.untarget(brick_id);
+                    if let Some(brick_id) = self.spiders.stolen_brick(spider_id) {
+                        self.letter_bricks.return_brick(brick_id);
+                        self.increase_score(RETURNED_BRICK_SCORE);
                     }
+                    self.spiders.kill(spider_id, &mut self.animations);
                 }
-                self.missile.terminate_flight();
-                let points = SPIDER_SCORE[self.spiders.spider_type(spider_id)];
-                self.increase_score(points);
-                if self.spiders.carrying(spider_id) {
-                    self.increase_score(points);
+                // only wounded
+                else if self.spiders.carrying(spider_id) && self.options.wounded_drop_brick {
+                    if let Some(brick_id) = target_brick_id {
+                        self.letter_bricks.untarget(brick_id);
+                    }
+                    self.spiders.drop_brick(spider_id, &mut self.base_bricks);
+                }
+            }
+        }
+    }
+
+    // only if the difficulty allows it
+    fn missile_bomb_collision(&mut self) {
+        for p in 0..self.game_mode.ships() {
+            for m in 0..MISSILES_PER_SHIP {
+                if self.options.shoot_bombs() && self.missiles[p][m].flying() &&
+                   self.bombs.shoot_down(&self.missiles[p][m].hitbox(), &mut self.animations) {
+                    self.missiles[p][m].terminate_flight();
+                    self.increase_score(BOMB_SCORE);
                 }
-                self.spiders.kill(spider_id, &mut self.animations);
             }
         }
     }
 
     fn bomb_collision(&mut self) {
-        if self.ship.alive() {
-            if self.bombs.collision(self.ship.area()) {
-                self.ship.kill(&mut self.animations);
+        // the cover is over the ships so it gets the first go at a bomb
+        if self.options.brick_cover {
+            self.bombs.cover_collision(&mut self.base_bricks, &mut self.animations);
+        }
+        for ship in self.ships.iter_mut().take(self.game_mode.ships()) {
+            if ship.alive() {
+                // a shield takes the hit instead of the ship
+                if self.bombs.collision(&ship.hitbox()) && ! self.power_ups.use_shield() {
+                    ship.kill(&mut self.animations, &mut self.effects);
+                }
             }
         }
     }
 
     fn spider_collision(&mut self) {
-        if self.ship.alive() {
-            if let Some(spider_id) = self.spiders.collision(self.ship.area()) {
-                let target_brick_id = self.spiders.target_brick_id(spider_id);
-                if let Some(brick_id) = target_brick_id {
-                    if self.spiders.carrying(spider_id) {
-                        self.letter_bricks.untarget(brick_id);
-                    }
-                    else {
-                        self.base_bricks.untarget(brick_id);
+        for p in 0..self.game_mode.ships() {
+            self.ship_spider_collision(p);
+            // a charging boss mother goes through a ship
+            if self.ships[p].alive() && self.mother.charge_collision(&self.ships[p].hitbox()) {
+                self.ships[p].kill(&mut self.animations, &mut self.effects);
+            }
+        }
+        while let Some(spider_id) = self.spiders.crashed() {
+            self.spiders.kill(spider_id, &mut self.animations);
+            self.effects.add_trauma(0.3);
+        }
+    }
+
+    fn ship_spider_collision(&mut self, p: usize) {
+        if self.ships[p].alive() {
+            if let Some(spider_id) = self.spiders.collision(&self.ships[p].hitbox()) {
+                self.remove_spider(spider_id);
+                self.ships[p].kill(&mut self.animations, &mut self.effects);
+            }
+        }
+    }
+
+    // a spider destroyed without being shot gives back whatever it had
+    // targetted or stolen
+    fn remove_spider(&mut self, spider_id: usize) {
+        if let Some(brick_id) = self.spiders.target_brick_id(spider_id) {
+            if self.spiders.targets_letter(spider_id) {
+                self.letter_bricks.untarget(brick_id);
+            }
+            else {
+                self.base_bricks.untarget(brick_id);
+            }
+        }
+        if let Some(brick_id) = self.spiders.stolen_brick(spider_id) {
+            self.letter_bricks.return_brick(brick_id);
+        }
+        self.spiders.kill(spider_id, &mut self.animations);
+    }
+
+    // missiles are stopped by the weak points of a boss mother, and all of
+    // its spiders go when the last one does
+    fn missile_mother_collision(&mut self) {
+        for p in 0..self.game_mode.ships() {
+            for m in 0..MISSILES_PER_SHIP {
+                let point = if self.missiles[p][m].flying()
+                    {self.mother.weak_point_collision(&self.missiles[p][m].hitbox())} else {None};
+                if let Some(point) = point {
+                    self.missiles[p][m].terminate_flight();
+                    if self.mother.hit(point, &mut self.animations) {
+                        self.increase_score(WEAK_POINT_SCORE);
+                        self.effects.add_trauma(0.3);
+                        if self.mother.defeated() {
+                            self.increase_score(BOSS_SCORE);
+                            self.effects.add_trauma(0.6);
+                            self.effects.flash(255, 200, 120);
+                            while let Some(spider_id) = self.spiders.flying() {
+                                self.remove_spider(spider_id);
+                            }
+                            self.spiders.abandon_nest();
+                        }
                     }
                 }
-                self.spiders.kill(spider_id, &mut self.animations);
-                self.ship.kill(&mut self.animations);
             }
         }
     }
 
     fn bonus_bomb_collision(&mut self) {
-        if self.missile.flying() && self.bonus_bomb.collision(self.missile.area()) {
-            self.missile.terminate_flight();
-            let points = self.bonus_bomb.score();
-            self.increase_score(points);
-            self.bonus_bomb.achieve_bonus(&mut self.letter_bricks, &mut self.animations);
+        for p in 0..self.game_mode.ships() {
+            for m in 0..MISSILES_PER_SHIP {
+                if self.missiles[p][m].flying() && self.bonus_bomb.collision(&self.missiles[p][m].hitbox()) {
+                    self.missiles[p][m].terminate_flight();
+                    let points = self.bonus_bomb.score();
+                    self.increase_score(points);
+                    self.bonus_bomb.achieve_bonus(&mut self.letter_bricks, &mut self.power_ups,
+                        &mut self.animations, &mut self.effects);
+                }
+            }
         }
     }
 
     pub fn render(&self, canvas: &mut Canvas<Window>) {
         canvas.clear();
+        self.effects.begin_render(canvas);
         self.background.render(canvas);
         self.render_screens_complete(canvas);
-        self.base_bricks.render(canvas);
+        self.base_bricks.render(canvas, &self.brick_image);
         self.letter_bricks.render(canvas);
         self.mother.render(canvas, self.frame_count);
         self.spiders.render(&self.mother, canvas, self.frame_count);
         self.bonus_bomb.render(canvas, self.frame_count);
         if self.game_state.playing() {
-            self.ship.render(canvas, self.frame_count);
-            self.missile.render(canvas);
+            for p in 0..self.game_mode.ships() {
+                self.ships[p].render(canvas, self.frame_count);
+                if self.ships[p].alive() {
+                    self.power_ups.render_shield(canvas, &self.ships[p].area(), self.frame_count);
+                }
+                for missile in &self.missiles[p] {
+                    missile.render(canvas);
+                }
+            }
+            self.power_ups.render_hud(canvas, &self.text24, self.frame_count);
+            self.base_bricks.render_supply(canvas, &self.text24);
+            self.mother.render_health(canvas);
         }
         self.bombs.render(canvas);
         self.render_score(canvas);
-        if let State::GameOver = self.game_state {
-            self.game_over_image.render(canvas, 87, 250);
+        match self.game_state {
+            State::GameOver => {
+                self.game_over_image.render(canvas, 87, 250);
+                if let Some(winner) = self.winner {
+                    self.text32.render(canvas, 200, 300, 79, 120, 181, 255,
+                        &format!("Player {} wins", winner + 1));
+                }
+                if let Some(ref standing) = self.daily_standing {
+                    self.text24.render(canvas, 140, 300, 79, 120, 181, 255, standing);
+                }
+            },
+            State::EnterName(player) => {
+                self.game_over_image.render(canvas, 87, 250);
+                self.render_name_entry(canvas, player);
+            },
+            State::PlayerReady(_) => {
+                self.text32.render(canvas, 200, 350, 79, 120, 181, 255,
+                    &format!("Player {} ready", self.current_player + 1));
+            },
+            _ => {},
+        }
+        if self.game_mode == GameMode::Versus && self.game_state.playing() {
+            self.render_versus(canvas);
+        }
+        if self.game_state.playing() {
+            self.render_clock(canvas);
+            self.render_challenge(canvas);
+        }
+        if self.game_state.accepting_start() {
+            if (self.frame_count / HIGH_SCORE_CYCLE) % 2 == 1 {
+                self.render_high_scores(canvas);
+            }
+            else {
+                self.instructions_image.render(canvas, 125, 320);
+            }
+            self.render_settings(canvas);
         }
-        if ! self.game_state.playing() {
-            self.instructions_image.render(canvas, 125, 320);
+        if let Some(status) = self.network_status {
+            self.text32.render(canvas, 20, 200, 79, 120, 181, 255, status);
         }
         self.animations.render(canvas);
+        #[cfg(feature = "debug-overlay")]
+        self.render_debug(canvas);
         if self.paused {
             self.text32.render(canvas, SCREEN_WIDTH as i32 / 2 - 55, 270,
                 0, 0, 255, 255, "Paused");
         }
+        self.effects.end_render(canvas, &self.options);
         canvas.present();
     }
 
+    fn update_options(&mut self) {
+        let on_off = |b: bool| if b {"on"} else {"off"};
+        let playing = self.game_state.playing();
+        let change =
+            if self.game_input.shake_option_pressed {
+                self.options.screen_shake = ! self.options.screen_shake;
+                Some(("Screen shake", on_off(self.options.screen_shake)))
+            }
+            else if self.game_input.hit_stop_option_pressed {
+                self.options.hit_stop = ! self.options.hit_stop;
+                Some(("Hit-stop", on_off(self.options.hit_stop)))
+            }
+            else if self.game_input.flash_option_pressed {
+                self.options.flashes = ! self.options.flashes;
+                Some(("Flashes", on_off(self.options.flashes)))
+            }
+            // the rules of the game can't be changed part way through
+            else if self.game_input.difficulty_option_pressed && ! playing {
+                self.options.difficulty = self.options.difficulty.next();
+                Some(("Difficulty", self.options.difficulty.name()))
+            }
+            else if self.game_input.mode_option_pressed && ! playing {
+                self.options.game_mode = self.options.game_mode.next();
+                Some(("Mode", self.options.game_mode.name()))
+            }
+            else if self.game_input.shared_lives_option_pressed && ! playing {
+                self.options.shared_lives = ! self.options.shared_lives;
+                Some(("Shared lives", on_off(self.options.shared_lives)))
+            }
+            else if self.game_input.shoot_bombs_option_pressed && ! playing {
+                let d = self.options.difficulty as usize;
+                self.options.shoot_bombs[d] = ! self.options.shoot_bombs[d];
+                Some(("Shoot bombs", on_off(self.options.shoot_bombs[d])))
+            }
+            else if self.game_input.cover_option_pressed && ! playing {
+                self.options.brick_cover = ! self.options.brick_cover;
+                Some(("Brick cover", on_off(self.options.brick_cover)))
+            }
+            else if self.game_input.rules_option_pressed && ! playing {
+                self.options.rules = self.options.rules.next();
+                Some(("Rules", self.options.rules.name()))
+            }
+            else {
+                None
+            };
+        self.game_input.acknowledge_option_change();
+        if let Some((name, value)) = change {
+            self.options.save();
+            let animation = Animation::new(AnimationSeq::OptionChange(name, value));
+            self.animations.register(animation);
+        }
+    }
+
+    fn render_settings(&self, canvas: &mut Canvas<Window>) {
+        let shoot_bombs = if self.options.shoot_bombs() {", shoot bombs"} else {""};
+        let shared_lives = if self.options.game_mode == GameMode::Cooperative && self.options.shared_lives
+            {", shared lives"} else {""};
+        let brick_cover = if self.options.brick_cover {", brick cover"} else {""};
+        let rules = if self.options.rules == Rules::Classic || self.options.game_mode == GameMode::Versus
+            {String::new()} else {format!(", {}", self.options.rules.name())};
+        self.text24.render(canvas, 10, 45, 79, 120, 181, 255,
+            &format!("{}{}, {}{}{}{}", self.options.game_mode.name(), rules, self.options.difficulty.name(),
+                shoot_bombs, shared_lives, brick_cover));
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        if ! self.debug_overlay.enabled() {
+            return;
+        }
+        self.base_bricks.render_debug(canvas);
+        self.letter_bricks.render_debug(canvas);
+        self.spiders.render_debug(canvas, self.debug_overlay.text());
+        self.bombs.render_debug(canvas);
+        self.bonus_bomb.render_debug(canvas);
+        self.mother.render_debug(canvas);
+        if self.game_state.playing() {
+            for p in 0..self.game_mode.ships() {
+                self.ships[p].hitbox().render_debug(canvas, Color::RGB(0, 255, 0));
+                for missile in self.missiles[p].iter().filter(|missile| missile.flying()) {
+                    debug_overlay::render_area(canvas, &missile.area(), Color::RGB(0, 255, 0));
+                }
+            }
+        }
+        self.debug_overlay.render_preview(canvas, self.frame_count);
+        self.debug_overlay.render(canvas);
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn record_timing(&mut self, frame_time: Duration, update_time: Duration) {
+        self.debug_overlay.record_timing(frame_time, update_time);
+    }
+
     pub fn update_inputs(&mut self, event: &Event) {
         self.game_input.update_inputs(event);
+        #[cfg(feature = "debug-overlay")]
+        self.debug_overlay.update_inputs(event);
     }
 
     pub fn update(&mut self) {
+        if self.spectating {
+            self.animations.update();
+            self.background.update();
+            return;
+        }
+        common::swap_random(&mut self.rng);
         if self.game_input.pause_pressed {
             self.game_input.acknowledge_pause();
-            if self.game_state.playing() {
+            // a network game can't be paused from one end
+            if self.game_state.playing() && self.network_player.is_none() {
                 self.paused = ! self.paused;
             }
         }
@@ -329,52 +1529,120 @@ impl<'a, 'b> Game<'a, 'b> {
             self.sound_control.increase_volume(&mut self.animations);
         }
 
+        if self.network_player.is_none() {
+            self.update_options();
+        }
+        else {
+            self.game_input.acknowledge_option_change();
+        }
+
+        let hit_stopped = self.effects.hit_stopped(&self.options);
         if ! self.paused {
+            self.effects.update(&self.options);
+        }
+
+        if ! self.paused && ! hit_stopped {
             self.frame_count += 1;
 
+            self.update_player_ready();
+            self.update_name_entry();
+            if self.challenge.is_some() && self.game_state.playing() {
+                let controls = if self.replaying {self.replay.next_controls()} else {self.game_input.take_controls()};
+                if ! self.replaying {
+                    self.replay.record(controls);
+                }
+                self.network_controls = [controls; 2];
+            }
+
             if self.game_state.playing() {
                 self.bonus_bomb_collision();
                 self.missile_collision();
+                self.missile_mother_collision();
+                self.missile_bomb_collision();
                 self.bomb_collision();
                 self.spider_collision();
-                if self.ship.waiting_for_changeover() &&
+                if self.game_mode == GameMode::Cooperative {
+                    if self.game_state.screen_in_progress() {
+                        self.coop_changeover();
+                    }
+                }
+                else if self.game_state.screen_in_progress() &&
+                    self.ships[0].waiting_for_changeover() &&
                     self.spiders.clear() && 
-                    ! self.bombs.in_flight() && ! self.missile.flying() &&
+                    ! self.bombs.in_flight() && ! self.missiles_flying(0) &&
                     ! self.bonus_bomb.in_flight() &&
-                    self.ship.enough_delay_for_changeover() {
-                    self.ship.proceed_with_changeover();
+                    self.ships[0].enough_delay_for_changeover() {
+                    if self.other_player_in_game() {
+                        self.switch_player();
+                    }
+                    else {
+                        self.ships[0].proceed_with_changeover();
+                    }
                 }
                 if self.letter_bricks.complete() {
-                    self.letter_bricks.initiate_expansion();
+                    match self.rules {
+                        Rules::Survival => {self.word_complete_in_survival();},
+                        Rules::TimeAttack => {self.word_complete_in_time_attack();},
+                        Rules::Classic => {},
+                    }
                 }
-                if self.letter_bricks.complete() || ! self.ship.life_left() {
-                    self.game_state = State::GameOver;
-                    self.spiders.turn_sound_off();
-                    self.bonus_bomb.turn_sound_off();
+                self.check_players_out();
+                if self.rules == Rules::TimeAttack && self.play_frames >= TIME_ATTACK_FRAMES &&
+                    self.game_state.screen_in_progress() {
+                    self.time_up();
                 }
 
-                self.missile.update();
-                self.ship.update();
-                if self.game_input.left_pressed {
-                    self.ship.move_left();
+                if self.game_mode == GameMode::Versus {
+                    self.update_mother_control();
                 }
-                else if self.game_input.right_pressed {
-                    self.ship.move_right();
+                if self.game_state.screen_in_progress() {
+                    self.power_ups.update();
+                    self.play_frames += 1;
                 }
-                if self.game_input.fire_pressed {
-                    self.ship.launch_missile(&mut self.missile);
-                    self.game_input.acknowledge_fire();
+                for p in 0..self.game_mode.ships() {
+                    for missile in &mut self.missiles[p] {
+                        missile.update();
+                    }
+                    self.ships[p].update();
+                    let (left, right, fire) = self.ship_controls(p);
+                    if left {
+                        self.ships[p].move_left();
+                    }
+                    else if right {
+                        self.ships[p].move_right();
+                    }
+                    if fire {
+                        self.ships[p].launch_missiles(&mut self.missiles[p], &self.power_ups);
+                        self.acknowledge_fire(p);
+                    }
                 }
             }
 
             if self.game_state.screen_in_progress() || ! self.game_state.playing() {
-                let restrict = (self.ship.in_changeover() && self.game_state.playing()) ||
-                    self.ship.protected();
+                // the spiders hold back only while every ship is out of action
+                let playing = self.game_state.playing();
+                let restrict = self.ships.iter().take(self.game_mode.ships())
+                    .all(|ship| (ship.in_changeover() && playing) || ship.protected());
                 self.base_bricks.update();
                 self.letter_bricks.update(self.frame_count);
                 self.bonus_bomb.update();
-                self.mother.update(&mut self.bonus_bomb, restrict, self.frame_count);
-                self.bombs.update();
+                let ship_xs: Vec<i32> = self.ships.iter().take(self.game_mode.ships())
+                    .filter(|ship| ship.alive() && playing)
+                    .map(|ship| ship.centre_x())
+                    .collect();
+                self.mother.update(&mut self.bonus_bomb, &mut self.bombs, &mut self.animations,
+                    &ship_xs, restrict, self.frame_count);
+                self.bombs.update(&ship_xs);
+                self.spiders.set_slowed(self.power_ups.active(PowerUp::SlowDown));
+                self.spiders.set_respawning(self.rules.respawning() && playing &&
+                    self.frame_count < TIME_ATTACK_ATTACK_FRAMES);
+                self.spiders.set_ship_positions(&ship_xs);
+                let missile_tips: Vec<(i32, i32)> = self.missiles.iter().take(self.game_mode.ships())
+                    .flatten()
+                    .filter(|missile| missile.flying())
+                    .map(|missile| missile.tip())
+                    .collect();
+                self.spiders.set_missile_positions(&missile_tips);
                 self.spiders.update(
                     &self.mother,
                     &mut self.base_bricks,
@@ -386,20 +1654,35 @@ impl<'a, 'b> Game<'a, 'b> {
 
             if self.game_state.screen_in_progress() &&
                 ! self.spiders.spiders_remain() &&
-                ! self.bombs.in_flight() {
-                self.screen += 1;
-                self.mother.reset();
+                ! self.bombs.in_flight() &&
+                self.game_mode == GameMode::Versus && self.screen == VERSUS_ATTACKS {
+                self.winner = Some(0);
+                self.game_over();
+            }
+            if self.game_state.screen_in_progress() &&
+                ! self.spiders.spiders_remain() &&
+                ! self.bombs.in_flight() &&
+                ! self.mother.defeat_in_progress() {
+                // beating a boss skips the attack after it
+                self.screen += if self.mother.defeated() {2} else {1};
+                self.mother.reset(self.screen);
                 self.bonus_bomb.reset();
                 self.spiders.reset(self.screen);
                 self.frame_count = 0;
                 self.screen_start();
             }
 
-            if (! self.game_state.playing()) && self.game_input.start_pressed {
+            if self.game_state.accepting_start() && self.game_input.start_pressed {
                 self.new_game();
             }
+            else if self.game_state.accepting_start() && self.game_input.daily_pressed &&
+                self.network_player.is_none() {
+                self.start_daily(Challenge::today(), None);
+            }
+            self.game_input.acknowledge_daily();
             self.animations.update();
             self.background.update();
         }
+        common::swap_random(&mut self.rng);
     }
 }
@@ -0,0 +1,62 @@
+// the different ways a game can be played
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum GameMode {
+    Single,
+    Alternating,
+    Cooperative,
+    Versus,
+}
+
+impl GameMode {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameMode::Single => {"One player"},
+            GameMode::Alternating => {"Two players"},
+            GameMode::Cooperative => {"Two players co-op"},
+            GameMode::Versus => {"Versus"},
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        match name {
+            "One player" => {Some(GameMode::Single)},
+            "Two players" => {Some(GameMode::Alternating)},
+            "Two players co-op" => {Some(GameMode::Cooperative)},
+            "Versus" => {Some(GameMode::Versus)},
+            _ => {None},
+        }
+    }
+
+    pub fn next(&self) -> GameMode {
+        match *self {
+            GameMode::Single => {GameMode::Alternating},
+            GameMode::Alternating => {GameMode::Cooperative},
+            GameMode::Cooperative => {GameMode::Versus},
+            GameMode::Versus => {GameMode::Single},
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        match *self {
+            GameMode::Single => {1},
+            GameMode::Alternating => {2},
+            GameMode::Cooperative => {2},
+            GameMode::Versus => {2},
+        }
+    }
+
+    // the number of ships on screen at once
+    pub fn ships(&self) -> usize {
+        match *self {
+            GameMode::Cooperative => {2},
+            _ => {1},
+        }
+    }
+
+    // whether either player's controls fly the one ship, otherwise each
+    // player has their own
+    pub fn controls_shared(&self) -> bool {
+        matches!(self, GameMode::Single | GameMode::Alternating)
+    }
+}
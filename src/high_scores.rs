@@ -0,0 +1,86 @@
+// high score tables, these persist between runs
+
+use std::fs;
+
+use crate::common;
+
+const TABLE_SIZE: usize = 10;
+pub const NAME_LENGTH: usize = 3;
+
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    // seconds survived, only kept in timed tables
+    pub seconds: u32,
+}
+
+pub struct HighScores {
+    file_name: &'static str,
+    timed: bool,
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    pub fn load(file_name: &'static str) -> HighScores {
+        HighScores::load_table(file_name, false)
+    }
+
+    // a table that also keeps how long each game lasted, as a third field
+    pub fn load_timed(file_name: &'static str) -> HighScores {
+        HighScores::load_table(file_name, true)
+    }
+
+    fn load_table(file_name: &'static str, timed: bool) -> HighScores {
+        let mut high_scores = HighScores {file_name, timed, entries: Vec::with_capacity(TABLE_SIZE + 1)};
+        if let Some(path) = common::config_path(file_name) {
+            if let Ok(contents) = fs::read_to_string(path) {
+                for line in contents.lines() {
+                    let (line, seconds) = match line.rsplit_once(' ') {
+                        Some((rest, seconds)) if timed => {(rest, seconds.parse().unwrap_or(0))},
+                        _ => {(line, 0)},
+                    };
+                    if let Some((name, score)) = line.rsplit_once(' ') {
+                        if let Ok(score) = score.parse() {
+                            high_scores.entries.push(HighScore {name: name.to_string(), score, seconds});
+                        }
+                    }
+                }
+            }
+        }
+        high_scores.entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+        high_scores.entries.truncate(TABLE_SIZE);
+        high_scores
+    }
+
+    fn save(&self) {
+        let contents: String = self.entries.iter()
+            .map(|e| if self.timed {format!("{} {} {}\n", e.name, e.score, e.seconds)}
+                else {format!("{} {}\n", e.name, e.score)})
+            .collect();
+        // losing the table is a shame but no reason to stop the game
+        if let Some(path) = common::config_path(self.file_name) {
+            let _res = fs::write(path, contents);
+        }
+    }
+
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 &&
+            (self.entries.len() < TABLE_SIZE || score > self.entries[TABLE_SIZE - 1].score)
+    }
+
+    pub fn insert(&mut self, name: &str, score: u32, seconds: u32) {
+        // equal scores go below those already there
+        let pos = self.entries.iter().position(|e| e.score < score).unwrap_or(self.entries.len());
+        self.entries.insert(pos, HighScore {name: name.to_string(), score, seconds});
+        self.entries.truncate(TABLE_SIZE);
+        self.save();
+    }
+
+    pub fn timed(&self) -> bool {
+        self.timed
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+}
@@ -1,18 +1,24 @@
-use rand;
-use rand::Rng;
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
+#[cfg(feature = "debug-overlay")]
+use sdl2::pixels::Color;
 
-use crate::common::TargetBrick;
+use crate::common::{self, TargetBrick};
 use crate::soundfx::SoundEffect;
 use crate::image::Image;
+use crate::spectator;
+use crate::targeting::{Candidate, Targeting, Random};
+#[cfg(feature = "debug-overlay")]
+use crate::common::ScreenObjectArea;
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay;
 
 const LETTER_BRICKS_Y: i32 = 140;
 const LETTER_BRICKS_X: i32 = 97;
 const BRICK_HEIGHT: u32 = 10;
 const BRICK_WIDTH: u32 = 15;
 const BRICKS_QTY: usize = 86;
-const MAX_COMBINED_ROW_BRICKS: usize = 23;
 const REMOVE_PERIOD: u32 = 15;
 
 pub struct LetterBrick {
@@ -28,6 +34,20 @@ impl LetterBrick {
     }
 }
 
+// the state of the word for one player, so that it can be put aside while the
+// other player takes their turn
+#[derive(Clone)]
+pub struct LetterProgress {
+    filled: [bool; BRICKS_QTY],
+    to_remove: Vec<usize>,
+}
+
+impl LetterProgress {
+    pub fn new() -> LetterProgress {
+        LetterProgress {filled: [false; BRICKS_QTY], to_remove: Vec::new()}
+    }
+}
+
 pub struct LetterBricks<'a> {
     letter_brick: [LetterBrick; BRICKS_QTY],
     letter_range: [[[usize; 2]; 6]; 3],
@@ -37,6 +57,11 @@ pub struct LetterBricks<'a> {
     remove_brick_sound: SoundEffect,
     expansion: f64,
     exp_step: f64,
+    // 0, 1 or 2 for F, I or N when a player is choosing which letter the
+    // spiders build next
+    preferred_letter: Option<usize>,
+    // how spiders choose which gap to fill
+    targeting: &'static dyn Targeting,
 }
 
 impl<'a> LetterBricks<'a> {
@@ -138,7 +163,7 @@ impl<'a> LetterBricks<'a> {
         qty_filled: 0,
         brick_image: Image::new(texture_creator, "letterbrick.png", BRICK_WIDTH - 2, BRICK_HEIGHT - 2),
         remove_brick_sound: SoundEffect::new("remove.ogg"),
-        expansion: 0.0, exp_step: 0.0,}
+        expansion: 0.0, exp_step: 0.0, preferred_letter: None, targeting: &Random,}
     }
 
     pub fn reset(&mut self) {
@@ -150,6 +175,32 @@ impl<'a> LetterBricks<'a> {
         self.to_remove.clear();
         self.expansion = 0.0;
         self.exp_step = 0.0;
+        self.preferred_letter = None;
+    }
+
+    // in survival a finished word is cleared to be built again, bricks
+    // already on their way keep their places
+    pub fn clear(&mut self) {
+        self.qty_filled = 0;
+        for b in &mut self.letter_brick.iter_mut() {
+            b.filled = false;
+        }
+        self.to_remove.clear();
+    }
+
+    pub fn set_preferred_letter(&mut self, letter: Option<usize>) {
+        self.preferred_letter = letter;
+    }
+
+    pub fn set_targeting(&mut self, targeting: &'static dyn Targeting) {
+        self.targeting = targeting;
+    }
+
+    fn letter_progress(&self, letter: usize) -> f64 {
+        let from = self.letter_range[letter][0][0];
+        let to = self.letter_range[letter][5][1];
+        let filled = (from..to + 1).filter(|&i| self.letter_brick[i].filled).count();
+        filled as f64 / (to + 1 - from) as f64
     }
 
     fn row_has_gaps(&self, letter: usize, row: usize) -> bool {
@@ -172,18 +223,65 @@ impl<'a> LetterBricks<'a> {
     }
 
     pub fn fill_target(&mut self, brick_id: usize) {
+        // a stolen brick may have been returned to the gap in the meantime
+        if ! self.letter_brick[brick_id].filled {
+            self.qty_filled += 1;
+        }
         self.letter_brick[brick_id].filled = true;
         self.letter_brick[brick_id].targetted = false;
-        self.qty_filled += 1;
+    }
+
+    fn brick_position(&self, id: usize) -> (i32, i32) {
+        (LETTER_BRICKS_X + self.letter_brick[id].col * BRICK_WIDTH as i32,
+         LETTER_BRICKS_Y + self.letter_brick[id].row * BRICK_HEIGHT as i32)
+    }
+
+    fn brick_target(&mut self, id: usize) -> TargetBrick {
+        let (x, y) = self.brick_position(id);
+        self.letter_brick[id].targetted = true;
+        TargetBrick {x, y, brick_id: id}
+    }
+
+    // a thief goes for a brick in a row of a letter that is already finished
+    pub fn request_theft(&mut self) -> Option<TargetBrick> {
+        let candidates: Vec<usize> = (0..3)
+            .flat_map(|i| (0..6).map(move |j| (i, j)))
+            .filter(|&(i, j)| ! self.row_has_gaps(i, j))
+            .flat_map(|(i, j)| self.letter_range[i][j][0]..self.letter_range[i][j][1] + 1)
+            .filter(|&k| ! self.letter_brick[k].targetted && ! self.to_remove.contains(&k))
+            .collect();
+        if candidates.is_empty() {
+            None
+        }
+        else {
+            let id = candidates[common::random_range(0..candidates.len())];
+            Some(self.brick_target(id))
+        }
+    }
+
+    pub fn take_brick(&mut self, brick_id: usize) {
+        if self.letter_brick[brick_id].filled {
+            self.letter_brick[brick_id].filled = false;
+            self.qty_filled -= 1;
+        }
+        self.letter_brick[brick_id].targetted = false;
+    }
+
+    // a brick won back from a thief
+    pub fn return_brick(&mut self, brick_id: usize) {
+        if ! self.letter_brick[brick_id].filled {
+            self.letter_brick[brick_id].filled = true;
+            self.qty_filled += 1;
+        }
     }
 
     pub fn untarget(&mut self, brick_id: usize) {
         self.letter_brick[brick_id].targetted = false;
     }
 
-    pub fn request_target(&mut self) -> Option<TargetBrick> {
-        let mut target_list = [0; MAX_COMBINED_ROW_BRICKS];  
-        let mut list_len: usize = 0;
+    // x, y is the top left of the spider asking
+    pub fn request_target(&mut self, x: f64, y: f64) -> Option<TargetBrick> {
+        let mut candidates: Vec<Candidate> = Vec::new();
 
         // build a list of gaps in the topmost row of each letter that has
         // unfilled bricks and is not physically next to another targetted gap
@@ -193,11 +291,12 @@ impl<'a> LetterBricks<'a> {
                     if self.row_has_gaps(i, j) {
                         let from = self.letter_range[i][j][0];
                         let to = self.letter_range[i][j][1];
+                        let progress = self.letter_progress(i);
                         for k in from..to + 1 {
                             if ! (self.letter_brick[k].filled || self.letter_brick[k].targetted) &&
                                ! (self.targetted_to_left(k) || self.targetted_to_right(k)) {
-                                target_list[list_len] = k;
-                                list_len += 1;
+                                let (brick_x, brick_y) = self.brick_position(k);
+                                candidates.push(Candidate {id: k, x: brick_x, y: brick_y, group: i, progress});
                             }
                         }
                         break;
@@ -205,19 +304,40 @@ impl<'a> LetterBricks<'a> {
                 }
             }
         }
-        // choose a random target from the list
-        if list_len > 0 {
-            let id = target_list[rand::thread_rng().gen_range(0..list_len)];
-            let x = LETTER_BRICKS_X + self.letter_brick[id].col * BRICK_WIDTH as i32;
-            let y = LETTER_BRICKS_Y + self.letter_brick[id].row * BRICK_HEIGHT as i32;
-            self.letter_brick[id].targetted = true;
-            Some(TargetBrick {x, y, brick_id: id})
+        // keep to the preferred letter while it still has gaps
+        if let Some(letter) = self.preferred_letter {
+            if candidates.iter().any(|c| c.group == letter) {
+                candidates.retain(|c| c.group == letter);
+            }
+        }
+        // and let the strategy for the attack choose from the list
+        if ! candidates.is_empty() {
+            let id = candidates[self.targeting.choose(&candidates, x, y)].id;
+            Some(self.brick_target(id))
         }
         else {
             None
         }
     }
 
+    pub fn progress(&self) -> LetterProgress {
+        let mut progress = LetterProgress::new();
+        for (i, b) in self.letter_brick.iter().enumerate() {
+            progress.filled[i] = b.filled;
+        }
+        progress.to_remove = self.to_remove.clone();
+        progress
+    }
+
+    pub fn restore_progress(&mut self, progress: &LetterProgress) {
+        self.reset();
+        for (i, b) in self.letter_brick.iter_mut().enumerate() {
+            b.filled = progress.filled[i];
+        }
+        self.qty_filled = progress.filled.iter().filter(|&&f| f).count();
+        self.to_remove = progress.to_remove.clone();
+    }
+
     pub fn initiate_removal(&mut self, qty: usize) {
         // build list of filled bricks
         for i in (0..BRICKS_QTY).rev() {
@@ -260,6 +380,28 @@ impl<'a> LetterBricks<'a> {
         }
     }
 
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        for b in &self.letter_brick {
+            (b.filled, b.targetted).hash(state);
+        }
+        (&self.to_remove, self.qty_filled, self.expansion.to_bits()).hash(state);
+    }
+
+    pub fn describe(&self) -> String {
+        let filled: Vec<bool> = self.letter_brick.iter().map(|b| b.filled).collect();
+        format!("{}:{:.2}", spectator::bits_to_hex(&filled), self.expansion)
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        let mut filled = [false; BRICKS_QTY];
+        spectator::hex_to_bits(fields.first().unwrap_or(&""), &mut filled);
+        for (b, &f) in self.letter_brick.iter_mut().zip(filled.iter()) {
+            b.filled = f;
+        }
+        self.expansion = spectator::field(&fields, 1, 0.0);
+    }
+
     pub fn render(&self, canvas: &mut Canvas<Window>) {
         if self.expansion == 0.0 {
             for b in self.letter_brick.iter().filter(|&b| b.filled) {
@@ -282,5 +424,14 @@ impl<'a> LetterBricks<'a> {
             }
         }
     }
-}
 
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        for b in self.letter_brick.iter().filter(|&b| b.targetted) {
+            let x = LETTER_BRICKS_X + b.col * BRICK_WIDTH as i32;
+            let y = LETTER_BRICKS_Y + b.row * BRICK_HEIGHT as i32;
+            let area = ScreenObjectArea::new(x, y, BRICK_WIDTH - 2, BRICK_HEIGHT - 2);
+            debug_overlay::render_area(canvas, &area, Color::RGB(255, 255, 0));
+        }
+    }
+}
@@ -10,14 +10,31 @@ mod letter_bricks;
 mod missile;
 mod game;
 mod bonus_bomb;
+mod power_ups;
+mod attacks;
 mod mother;
 mod spiders;
+mod steering;
+mod paths;
+mod targeting;
 mod bombs;
 mod background;
 mod soundfx;
 mod image;
 mod text;
 mod animation;
+mod options;
+mod difficulty;
+mod game_mode;
+mod rules;
+mod daily;
+mod high_scores;
+mod effects;
+mod collision;
+mod netplay;
+mod spectator;
+#[cfg(feature = "debug-overlay")]
+mod debug_overlay;
 
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
@@ -25,11 +42,16 @@ use sdl2::mixer::{DEFAULT_CHANNELS, InitFlag, AUDIO_S16LSB, Channel};
 use sdl2::render::BlendMode::Blend;
 use std::time::{Duration, Instant};
 use std::thread::sleep;
+use std::env;
+use std::process;
 
 use crate::game::Game;
+use crate::spectator::{SpectatorInput, SpectatorOutput};
 use crate::common::{SCREEN_WIDTH, SCREEN_HEIGHT, UPDATE_FPS};
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+
     let sdl_context = sdl2::init().unwrap();
     let video_subsys = sdl_context.video().unwrap();
     let window = video_subsys.window("FIN (sdl)", SCREEN_WIDTH, SCREEN_HEIGHT)
@@ -44,6 +66,10 @@ fn main() {
 
     let mut events = sdl_context.event_pump().unwrap();
 
+    // controllers must be kept open for their events to arrive
+    let controller_subsys = sdl_context.game_controller().unwrap();
+    let mut controllers = Vec::new();
+
     let ttf_context = sdl2::ttf::init().unwrap();
 
     let _audio = sdl_context.audio().unwrap();
@@ -57,9 +83,41 @@ fn main() {
     Channel::all().set_volume(0);
 
     let mut game = Game::new(&texture_creator, &ttf_context);
+    let mut session = netplay::session_from_args(&args, &game);
+    let arg_value = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+    let mut spectator_output = arg_value("--spectator-out").and_then(|target| {
+        SpectatorOutput::open(target).map_err(|e| eprintln!("{}", e)).ok()
+    });
+    let mut spectator_input = match arg_value("--spectate") {
+        Some(source) => {
+            match SpectatorInput::open(source) {
+                Ok(input) => {
+                    game.start_spectating();
+                    Some(input)
+                },
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                },
+            }
+        },
+        None => {None},
+    };
+    if let Some(file_name) = arg_value("--replay") {
+        match daily::load_replay(file_name) {
+            Ok((challenge, replay)) => {game.start_replay(challenge, replay);},
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            },
+        }
+    }
 
     let tick_length = Duration::new(0, 1_000_000_000u32 / UPDATE_FPS);
 
+    #[cfg(feature = "debug-overlay")]
+    let mut last_frame_start = Instant::now();
+
     'main: loop {
         let now = Instant::now();
 
@@ -73,11 +131,40 @@ fn main() {
                         break 'main
                     }
                 },
+                // sent for each controller present at startup as well as any plugged in later
+                Event::ControllerDeviceAdded {which, ..} => {
+                    if let Ok(controller) = controller_subsys.open(which) {
+                        controllers.push(controller);
+                    }
+                },
                 _ => {}
             }
         }
 
-        game.update();
+        #[cfg(feature = "debug-overlay")]
+        let update_start = Instant::now();
+        match session {
+            Some(ref mut s) => {
+                s.tick(&mut game, |game| game.take_local_controls());
+                if s.finished() {
+                    session = None;
+                }
+            },
+            None => {game.update();},
+        }
+        if let Some(ref mut input) = spectator_input {
+            if let Some(frame) = input.next_frame() {
+                game.watch(&frame);
+            }
+        }
+        if let Some(ref mut output) = spectator_output {
+            output.write_frame(&game.describe());
+        }
+        #[cfg(feature = "debug-overlay")]
+        {
+            game.record_timing(now - last_frame_start, update_start.elapsed());
+            last_frame_start = now;
+        }
         game.render(&mut canvas);
 
         // we try to stick to the desired timing - if we overrun, things will slooow down
@@ -1,9 +1,12 @@
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
 
 use crate::common::ScreenObjectArea;
 use crate::soundfx::SoundEffect;
 use crate::image::Image;
+use crate::spectator;
+use crate::collision::Hitbox;
 
 const MISSILE_WIDTH: u32 = 3;
 const MISSILE_HEIGHT: u32 = 15;
@@ -12,15 +15,20 @@ const MISSILE_SPEED: i32 = 12;
 pub struct Missile<'a> {
     x: i32,
     y: i32,
+    prev_y: i32,
     in_flight: bool,
+    // a piercing missile carries on until it has hit this many spiders
+    hits_left: u32,
     missile_image: Image<'a>,
+    piercing_image: Image<'a>,
     fire_sound: SoundEffect,
 }
 
 impl<'a> Missile<'a> {
     pub fn new(texture_creator: &TextureCreator<WindowContext>) -> Missile {
-        Missile {x: 0, y: 0, in_flight: false,
+        Missile {x: 0, y: 0, prev_y: 0, in_flight: false, hits_left: 1,
             missile_image: Image::new(texture_creator, "missile.png", MISSILE_WIDTH, MISSILE_HEIGHT),
+            piercing_image: Image::new(texture_creator, "missile_pierce.png", MISSILE_WIDTH, MISSILE_HEIGHT),
             fire_sound: SoundEffect::new("fire.ogg")}
     }
 
@@ -28,16 +36,19 @@ impl<'a> Missile<'a> {
         self.in_flight = false;
     }
 
-    pub fn launch(&mut self, from_x: i32, from_y: i32) {
+    pub fn launch(&mut self, from_x: i32, from_y: i32, hits: u32) {
         if ! self.in_flight {
+            self.hits_left = hits;
             self.x = from_x - (MISSILE_WIDTH / 2) as i32;
             self.y = from_y - MISSILE_HEIGHT as i32;
+            self.prev_y = self.y;
             self.in_flight = true;
             self.fire_sound.play();
         }
     }
 
     pub fn update(&mut self) {
+        self.prev_y = self.y;
         if self.in_flight {
             self.y -= MISSILE_SPEED;
         }
@@ -48,6 +59,16 @@ impl<'a> Missile<'a> {
 
     pub fn area(&self) -> ScreenObjectArea {
         ScreenObjectArea::new(self.x, self.y, MISSILE_WIDTH, MISSILE_HEIGHT)
+            .moving(0, self.y - self.prev_y)
+    }
+
+    // the middle of the front of the missile
+    pub fn tip(&self) -> (i32, i32) {
+        (self.x + MISSILE_WIDTH as i32 / 2, self.y)
+    }
+
+    pub fn hitbox(&self) -> Hitbox<'_> {
+        Hitbox::new(self.area())
     }
 
     pub fn flying(&self) -> bool {
@@ -58,9 +79,35 @@ impl<'a> Missile<'a> {
         self.in_flight = false;
     }
 
+    pub fn hit_spider(&mut self) {
+        if self.hits_left > 1 {
+            self.hits_left -= 1;
+        }
+        else {
+            self.in_flight = false;
+        }
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        (self.x, self.y, self.in_flight, self.hits_left).hash(state);
+    }
+
+    pub fn describe(&self) -> String {
+        if self.in_flight {format!("{}:{}:{}", self.x, self.y, self.hits_left)} else {String::from("-")}
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        self.in_flight = fields.len() == 3;
+        self.x = spectator::field(&fields, 0, self.x);
+        self.y = spectator::field(&fields, 1, self.y);
+        self.hits_left = spectator::field(&fields, 2, 1);
+    }
+
     pub fn render(&self, canvas: &mut Canvas<Window>) {
         if self.in_flight {
-            self.missile_image.render(canvas, self.x, self.y);
+            let image = if self.hits_left > 1 {&self.piercing_image} else {&self.missile_image};
+            image.render(canvas, self.x, self.y);
         }
     }
 }
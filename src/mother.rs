@@ -1,11 +1,21 @@
-use rand;
-use rand::Rng;
+use std::f64::consts::FRAC_PI_2;
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
+use sdl2::rect::{Point, Rect};
+use sdl2::pixels::Color;
 
-use crate::common::SCREEN_WIDTH;
+use crate::common::{self, ScreenObjectArea, SCREEN_WIDTH};
+use crate::attacks;
 use crate::bonus_bomb::BonusBomb;
+use crate::bombs::Bombs;
 use crate::image::Image;
+use crate::spectator;
+use crate::collision::Hitbox;
+use crate::soundfx::SoundEffect;
+use crate::animation::{AnimationSeq, Animation, Animations};
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay;
 
 const MOTHER_Y: i32 = 100;
 const MOTHER_WIDTH: u32 = 100;
@@ -13,44 +23,145 @@ const MOTHER_HEIGHT: u32 = 20;
 const MOTHER_PERIOD: u32 = 32;
 const MOTHER_SPEED: i32 = 2;
 
+// the boss mother, see attacks::boss_attack
+const BOSS_SPEED: i32 = 3;
+const WEAK_POINTS: usize = 3;
+const WEAK_POINT_HITS: u32 = 8;
+const WEAK_POINT_X: [i32; WEAK_POINTS] = [10, 43, 76];
+const WEAK_POINT_WIDTH: u32 = 14;
+const WEAK_POINT_HEIGHT: u32 = 10;
+const WEAK_POINT_FLASH_FRAMES: u32 = 6;
+// time between attack patterns, shorter for each weak point lost
+const CRUISE_FRAMES: u32 = 240;
+const CRUISE_FRAMES_PER_WEAK_POINT: u32 = 60;
+const FIRST_CRUISE_FRAMES: u32 = 300;
+const BURST_FRAMES: u32 = 180;
+const SPREAD_FRAMES: u32 = 120;
+const SPREAD_SPEED: i32 = 5;
+const SPREAD_OFFSETS: [i32; 3] = [-40, 0, 40];
+const REPOSITION_SPEED: i32 = 5;
+const TELEGRAPH_FRAMES: u32 = 45;
+const CHARGE_SPEED: i32 = 7;
+const CHARGE_Y: i32 = 550;
+const CHARGE_LEFT: i32 = MOTHER_WIDTH as i32 / 2 + 10;
+const CHARGE_RIGHT: i32 = SCREEN_WIDTH as i32 - MOTHER_WIDTH as i32 / 2 - 10;
+const CHARGE_MIN_RUN: i32 = 80;
+const DEFEAT_FRAMES: u32 = 150;
+const DEFEAT_EXPLOSION_PERIOD: u32 = 10;
+const HEALTH_BAR_X: i32 = 200;
+const HEALTH_BAR_Y: i32 = 44;
+const HEALTH_BAR_WIDTH: u32 = 200;
+const HEALTH_BAR_HEIGHT: u32 = 8;
+
+#[derive(Copy, Clone, PartialEq, Hash)]
+enum Pattern {
+    Cruise(u32),
+    Burst(u32),
+    Spread(u32),
+    Reposition(i32),
+    Telegraph(u32, i32),
+    Charge(i32, i32, i32),
+    Defeated(u32),
+}
+
 pub struct Mother<'a> {
     x: i32,
+    y: i32,
     vel: i32,
     mother_image1: Image<'a>,
     mother_image2: Image<'a>,
+    boss_image1: Image<'a>,
+    boss_image2: Image<'a>,
     bonus_bomb_frame: u32,
+    // when a player controls the mother, spiders only launch in the
+    // direction they ask for and they can drop the bonus bomb early
+    controlled: bool,
+    launch_request: Option<i32>,
+    bonus_bomb_requested: bool,
+    // every few attacks the mother fights back until its weak points are
+    // shot away
+    boss: bool,
+    pattern: Pattern,
+    weak_points: [u32; WEAK_POINTS],
+    weak_point_flash: [u32; WEAK_POINTS],
+    hit_sound: SoundEffect,
+    explosion_sound: SoundEffect,
+    sound_on: bool,
 }
 
 impl<'a> Mother<'a> {
     pub fn new(texture_creator: &TextureCreator<WindowContext>) -> Mother {
         Mother {
             x: (SCREEN_WIDTH - MOTHER_WIDTH) as i32 / 2,
+            y: MOTHER_Y,
             vel: MOTHER_SPEED,
             mother_image1: Image::new(texture_creator, "mother1.png", MOTHER_WIDTH, MOTHER_HEIGHT),
             mother_image2: Image::new(texture_creator, "mother2.png", MOTHER_WIDTH, MOTHER_HEIGHT),
-            bonus_bomb_frame: 0}
+            boss_image1: Image::new(texture_creator, "mother_boss1.png", MOTHER_WIDTH, MOTHER_HEIGHT),
+            boss_image2: Image::new(texture_creator, "mother_boss2.png", MOTHER_WIDTH, MOTHER_HEIGHT),
+            bonus_bomb_frame: 0,
+            controlled: false,
+            launch_request: None,
+            bonus_bomb_requested: false,
+            boss: false,
+            pattern: Pattern::Cruise(0),
+            weak_points: [0; WEAK_POINTS],
+            weak_point_flash: [0; WEAK_POINTS],
+            hit_sound: SoundEffect::new("boss_hit.wav"),
+            explosion_sound: SoundEffect::new("boss_explosion.wav"),
+            sound_on: false}
     }
 
     pub fn full_reset(&mut self) {
-        self.reset();
+        self.reset(1);
         self.x = (SCREEN_WIDTH - MOTHER_WIDTH) as i32 / 2;
         self.vel = MOTHER_SPEED;
     }
 
-    pub fn reset(&mut self) {
+    pub fn reset(&mut self, screen: u32) {
         // bonus bomb 20 - 40 sec in
-        self.bonus_bomb_frame = 1200 + rand::thread_rng().gen_range(0..1200);
+        self.bonus_bomb_frame = 1200 + common::random_range(0..1200);
+        self.launch_request = None;
+        self.bonus_bomb_requested = false;
+        // in versus the mother is the second player's, so never a boss
+        self.boss = ! self.controlled && attacks::boss_attack(screen);
+        self.y = MOTHER_Y;
+        self.pattern = Pattern::Cruise(FIRST_CRUISE_FRAMES);
+        self.weak_points = [if self.boss {WEAK_POINT_HITS} else {0}; WEAK_POINTS];
+        self.weak_point_flash = [0; WEAK_POINTS];
+        let speed = if self.boss {BOSS_SPEED} else {MOTHER_SPEED};
+        self.vel = self.vel.signum() * speed;
     }
 
-    pub fn update(&mut self, bonus_bomb: &mut BonusBomb, restrict: bool, frame_count: u32) {
-        self.x += self.vel;
-        if self.x > (SCREEN_WIDTH - MOTHER_WIDTH) as i32 - MOTHER_SPEED ||
-           self.x < MOTHER_SPEED {
-            self.vel = - self.vel;
+    pub fn set_controlled(&mut self, controlled: bool) {
+        self.controlled = controlled;
+    }
+
+    // -1 for left, 1 for right, None to hold the spiders back
+    pub fn request_launch(&mut self, dir: Option<i32>) {
+        self.launch_request = dir;
+    }
+
+    pub fn request_bonus_bomb(&mut self) {
+        self.bonus_bomb_requested = true;
+    }
+
+    pub fn update(&mut self, bonus_bomb: &mut BonusBomb, bombs: &mut Bombs, animations: &mut Animations,
+                  ship_xs: &[i32], restrict: bool, frame_count: u32) {
+        if self.boss {
+            self.update_boss(bombs, animations, ship_xs, restrict);
         }
+        else {
+            self.cruise();
+        }
+        // the bomb still drops by itself if the player holds on to it
+        if self.bonus_bomb_requested && ! restrict && frame_count < self.bonus_bomb_frame {
+            self.bonus_bomb_frame = frame_count;
+        }
+        self.bonus_bomb_requested = false;
         if frame_count == self.bonus_bomb_frame {
-            if restrict {
-                self.bonus_bomb_frame += 100 + rand::thread_rng().gen_range(0..200);  // postpone it
+            if restrict || self.y != MOTHER_Y || self.defeated() {
+                self.bonus_bomb_frame += 100 + common::random_range(0..200);  // postpone it
             }
             else {
                 bonus_bomb.launch(self.x + MOTHER_WIDTH as i32 / 2);
@@ -58,14 +169,162 @@ impl<'a> Mother<'a> {
         }
     }
 
+    fn cruise(&mut self) {
+        let speed = self.vel.abs();
+        self.x += self.vel;
+        if (self.vel > 0 && self.x > (SCREEN_WIDTH - MOTHER_WIDTH) as i32 - speed) ||
+           (self.vel < 0 && self.x < speed) {
+            self.vel = - self.vel;
+        }
+    }
+
+    fn centre_x(&self) -> i32 {
+        self.x + MOTHER_WIDTH as i32 / 2
+    }
+
+    fn nearest_ship(&self, ship_xs: &[i32]) -> Option<i32> {
+        let centre = self.centre_x();
+        ship_xs.iter().copied().min_by_key(|x| (x - centre).abs())
+    }
+
+    fn weak_points_lost(&self) -> u32 {
+        self.weak_points.iter().filter(|&&hits| hits == 0).count() as u32
+    }
+
+    fn cruise_pattern(&self) -> Pattern {
+        Pattern::Cruise(CRUISE_FRAMES - CRUISE_FRAMES_PER_WEAK_POINT * self.weak_points_lost())
+    }
+
+    // charges start from the nearer side of the screen
+    fn next_pattern(&self) -> Pattern {
+        match common::random_range(0..3) {
+            0 => {Pattern::Burst(BURST_FRAMES)},
+            1 => {Pattern::Spread(SPREAD_FRAMES)},
+            _ => {
+                let start = if self.centre_x() < SCREEN_WIDTH as i32 / 2 {CHARGE_LEFT} else {CHARGE_RIGHT};
+                Pattern::Reposition(start)
+            },
+        }
+    }
+
+    fn update_boss(&mut self, bombs: &mut Bombs, animations: &mut Animations,
+                   ship_xs: &[i32], restrict: bool) {
+        for flash in self.weak_point_flash.iter_mut().filter(|f| **f > 0) {
+            *flash -= 1;
+        }
+        self.pattern = match self.pattern {
+            Pattern::Cruise(n) => {
+                self.cruise();
+                if n > 0 {Pattern::Cruise(n - 1)}
+                else if restrict {Pattern::Cruise(0)}   // hold back while the ships are out
+                else {self.next_pattern()}
+            },
+            Pattern::Burst(n) => {
+                self.cruise();
+                if n > 0 {Pattern::Burst(n - 1)} else {self.cruise_pattern()}
+            },
+            Pattern::Spread(n) => {
+                // line up over the nearest ship then drop bombs either side
+                // of it as well as on it
+                match self.nearest_ship(ship_xs) {
+                    Some(ship_x) => {
+                        let centre = self.centre_x();
+                        let dx = (ship_x - centre).clamp(-SPREAD_SPEED, SPREAD_SPEED);
+                        self.x = (self.x + dx).clamp(0, (SCREEN_WIDTH - MOTHER_WIDTH) as i32);
+                        if dx.abs() < SPREAD_SPEED || n == 0 {
+                            if ! restrict {
+                                for offset in SPREAD_OFFSETS {
+                                    bombs.release(self.centre_x() + offset, self.y + MOTHER_HEIGHT as i32);
+                                }
+                            }
+                            self.cruise_pattern()
+                        }
+                        else {
+                            Pattern::Spread(n - 1)
+                        }
+                    },
+                    None => {self.cruise_pattern()},
+                }
+            },
+            Pattern::Reposition(start) => {
+                let dx = (start - self.centre_x()).clamp(-REPOSITION_SPEED, REPOSITION_SPEED);
+                self.x += dx;
+                if dx != 0 {
+                    Pattern::Reposition(start)
+                }
+                else {
+                    // the target is fixed now so that there is time to get
+                    // out of the way
+                    match self.nearest_ship(ship_xs) {
+                        Some(ship_x) => {
+                            let target = ship_x.clamp(CHARGE_LEFT + CHARGE_MIN_RUN, CHARGE_RIGHT - CHARGE_MIN_RUN);
+                            Pattern::Telegraph(TELEGRAPH_FRAMES, target)
+                        },
+                        None => {self.cruise_pattern()},
+                    }
+                }
+            },
+            Pattern::Telegraph(n, target) => {
+                if n > 0 {
+                    Pattern::Telegraph(n - 1, target)
+                }
+                else {
+                    let start = self.centre_x();
+                    let end = if start < SCREEN_WIDTH as i32 / 2 {CHARGE_RIGHT} else {CHARGE_LEFT};
+                    Pattern::Charge(start, target, end)
+                }
+            },
+            Pattern::Charge(start, target, end) => {
+                // a quarter sine wave down to the target and another back up
+                let centre = self.centre_x() + (end - self.centre_x()).clamp(-CHARGE_SPEED, CHARGE_SPEED);
+                self.x = centre - MOTHER_WIDTH as i32 / 2;
+                let depth = if (centre - start).abs() < (target - start).abs() {
+                    ((centre - start) as f64 / (target - start) as f64 * FRAC_PI_2).sin()
+                }
+                else {
+                    ((end - centre) as f64 / (end - target) as f64 * FRAC_PI_2).sin()
+                };
+                self.y = MOTHER_Y + ((CHARGE_Y - MOTHER_Y) as f64 * depth) as i32;
+                if centre == end {
+                    self.y = MOTHER_Y;
+                    self.vel = (start - end).signum() * BOSS_SPEED;
+                    self.cruise_pattern()
+                }
+                else {
+                    Pattern::Charge(start, target, end)
+                }
+            },
+            Pattern::Defeated(n) => {
+                if n > 0 && n % DEFEAT_EXPLOSION_PERIOD == 0 {
+                    let x = self.x + common::random_range(0..MOTHER_WIDTH as i32 - 20);
+                    let y = self.y + common::random_range(-10..10);
+                    animations.register(Animation::new(AnimationSeq::SpiderExplosion(x, y)));
+                }
+                Pattern::Defeated(n.saturating_sub(1))
+            },
+        };
+    }
+
     pub fn location(&self) -> (i32, i32) {
-        (self.x, MOTHER_Y)
+        (self.x, self.y)
     }
 
     pub fn launch_dir(&self) -> Option<i32> {
+        // a boss only lets spiders go while it is up top
+        match self.pattern {
+            Pattern::Cruise(_) | Pattern::Burst(_) | Pattern::Spread(_) => {},
+            _ => {return None;},
+        }
         // to launch a spider with a clean exit, we want to launch in the
         // opposite direction to the mother but only if there is enough space
         // between the mother and the edge of the screen to allow a swoop
+        if self.controlled {
+            return match self.launch_request {
+                Some(d) if (d > 0 && self.x < (SCREEN_WIDTH - 50 - MOTHER_WIDTH) as i32) ||
+                           (d < 0 && self.x > 50) => {Some(d)},
+                _ => {None},
+            };
+        }
         if (self.vel < 0 && self.x < (SCREEN_WIDTH - 50 - MOTHER_WIDTH) as i32) ||
            (self.vel > 0 && self.x > 50) {
             Some(- self.vel.signum())
@@ -75,9 +334,179 @@ impl<'a> Mother<'a> {
         }
     }
 
+    // spiders leave the nest in quick succession
+    pub fn bursting(&self) -> bool {
+        matches!(self.pattern, Pattern::Burst(_))
+    }
+
+    pub fn turn_sound_on(&mut self) {
+        self.sound_on = true;
+    }
+
+    pub fn turn_sound_off(&mut self) {
+        self.sound_on = false;
+    }
+
+    pub fn defeated(&self) -> bool {
+        self.boss && self.weak_points.iter().all(|&hits| hits == 0)
+    }
+
+    pub fn defeat_in_progress(&self) -> bool {
+        if let Pattern::Defeated(n) = self.pattern {n > 0} else {false}
+    }
+
+    fn area(&self) -> ScreenObjectArea {
+        ScreenObjectArea::new(self.x, self.y, MOTHER_WIDTH, MOTHER_HEIGHT + WEAK_POINT_HEIGHT)
+    }
+
+    fn weak_point_area(&self, point: usize) -> ScreenObjectArea {
+        ScreenObjectArea::new(self.x + WEAK_POINT_X[point], self.y + MOTHER_HEIGHT as i32,
+            WEAK_POINT_WIDTH, WEAK_POINT_HEIGHT)
+    }
+
+    pub fn weak_point_collision(&self, hitbox: &Hitbox) -> Option<usize> {
+        if ! self.boss || self.defeated() {
+            return None;
+        }
+        (0..WEAK_POINTS).find(|&point| self.weak_points[point] > 0 &&
+            Hitbox::new(self.weak_point_area(point)).collides(hitbox))
+    }
+
+    // only a charging mother is dangerous to touch
+    pub fn charge_collision(&self, hitbox: &Hitbox) -> bool {
+        if let Pattern::Charge(_, _, _) = self.pattern {
+            Hitbox::new(self.area()).collides(hitbox)
+        }
+        else {
+            false
+        }
+    }
+
+    // true when the hit destroyed the weak point
+    pub fn hit(&mut self, point: usize, animations: &mut Animations) -> bool {
+        self.weak_points[point] -= 1;
+        self.weak_point_flash[point] = WEAK_POINT_FLASH_FRAMES;
+        if self.sound_on {
+            self.hit_sound.play();
+        }
+        if self.weak_points[point] > 0 {
+            return false;
+        }
+        let (x, y) = self.weak_point_area(point).position();
+        animations.register(Animation::new(AnimationSeq::SpiderExplosion(x - 8, y - 12)));
+        if self.defeated() {
+            self.pattern = Pattern::Defeated(DEFEAT_FRAMES);
+            if self.sound_on {
+                self.explosion_sound.play();
+            }
+        }
+        true
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        (self.x, self.y, self.vel, self.bonus_bomb_frame).hash(state);
+        (self.boss, self.pattern, self.weak_points).hash(state);
+    }
+
+    // the pattern is only described as far as it changes what is drawn
+    pub fn describe(&self) -> String {
+        let (code, n) = match self.pattern {
+            _ if ! self.boss => {("-", 0)},
+            Pattern::Telegraph(_, target) => {("T", target)},
+            Pattern::Charge(_, _, _) => {("X", 0)},
+            Pattern::Defeated(n) => {("D", n as i32)},
+            _ => {("C", 0)},
+        };
+        let weak_points: Vec<String> = self.weak_points.iter().map(|w| w.to_string()).collect();
+        format!("{}:{}:{}:{}:{}", self.x, self.y, code, n, weak_points.join(":"))
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        self.x = spectator::field(&fields, 0, self.x);
+        self.y = spectator::field(&fields, 1, MOTHER_Y);
+        let n = spectator::field(&fields, 3, 0);
+        self.boss = true;
+        self.pattern = match fields.get(2) {
+            Some(&"T") => {Pattern::Telegraph(0, n)},
+            Some(&"X") => {Pattern::Charge(0, 0, 0)},
+            Some(&"D") => {Pattern::Defeated(n as u32)},
+            Some(&"C") => {Pattern::Cruise(0)},
+            _ => {self.boss = false; Pattern::Cruise(0)},
+        };
+        for (point, hits) in self.weak_points.iter_mut().enumerate() {
+            *hits = spectator::field(&fields, 4 + point, 0);
+        }
+    }
+
     pub fn render(&self, canvas: &mut Canvas<Window>, frame_count: u32) {
+        let mut x = self.x;
+        match self.pattern {
+            Pattern::Telegraph(_, target) => {
+                // shudder and mark where the charge will bottom out
+                x += if (frame_count / 2).is_multiple_of(2) {2} else {-2};
+                Mother::render_charge_line(canvas, target);
+            },
+            Pattern::Defeated(0) => {return;},
+            Pattern::Defeated(n) => {
+                if (n / 4) % 2 == 0 {
+                    return;
+                }
+                x += (n % 3) as i32 * 3 - 3;
+            },
+            _ => {},
+        }
+        let (image1, image2) = if self.boss
+            {(&self.boss_image1, &self.boss_image2)} else {(&self.mother_image1, &self.mother_image2)};
         let mother_image = if frame_count % MOTHER_PERIOD < MOTHER_PERIOD / 2
-            {&self.mother_image1} else {&self.mother_image2};
-        mother_image.render(canvas, self.x, MOTHER_Y);
+            {image1} else {image2};
+        mother_image.render(canvas, x, self.y);
+        if self.boss {
+            self.render_weak_points(canvas, x - self.x, frame_count);
+        }
+    }
+
+    fn render_weak_points(&self, canvas: &mut Canvas<Window>, offset: i32, frame_count: u32) {
+        let glow = (frame_count % MOTHER_PERIOD) as u8 * 3;
+        for point in 0..WEAK_POINTS {
+            let colour = if self.weak_points[point] == 0 {Color::RGB(60, 60, 60)}
+                else if self.weak_point_flash[point] > 0 {Color::RGB(255, 255, 255)}
+                else {Color::RGB(255, 150 + glow, 40)};
+            let (x, y) = self.weak_point_area(point).position();
+            canvas.set_draw_color(colour);
+            let _ = canvas.fill_rect(Rect::new(x + offset, y, WEAK_POINT_WIDTH, WEAK_POINT_HEIGHT));
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    fn render_charge_line(canvas: &mut Canvas<Window>, x: i32) {
+        canvas.set_draw_color(Color::RGBA(255, 80, 40, 160));
+        for y in (MOTHER_Y + 40..CHARGE_Y + 40).step_by(12) {
+            let _ = canvas.draw_line(Point::new(x, y), Point::new(x, y + 6));
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    pub fn render_health(&self, canvas: &mut Canvas<Window>) {
+        if ! self.boss || self.defeated() {
+            return;
+        }
+        let hits: u32 = self.weak_points.iter().sum();
+        let width = HEALTH_BAR_WIDTH * hits / (WEAK_POINT_HITS * WEAK_POINTS as u32);
+        canvas.set_draw_color(Color::RGB(220, 40, 30));
+        let _ = canvas.fill_rect(Rect::new(HEALTH_BAR_X, HEALTH_BAR_Y, width.max(1), HEALTH_BAR_HEIGHT));
+        canvas.set_draw_color(Color::RGB(79, 120, 181));
+        let _ = canvas.draw_rect(Rect::new(HEALTH_BAR_X - 1, HEALTH_BAR_Y - 1,
+            HEALTH_BAR_WIDTH + 2, HEALTH_BAR_HEIGHT + 2));
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>) {
+        if self.boss {
+            for point in (0..WEAK_POINTS).filter(|&point| self.weak_points[point] > 0) {
+                debug_overlay::render_area(canvas, &self.weak_point_area(point), Color::RGB(255, 0, 0));
+            }
+        }
     }
 }
@@ -0,0 +1,494 @@
+// lockstep network play - both games run the same simulation from the same
+// seed, so all they need to exchange are the controls for each frame
+
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::game::Game;
+
+const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_PORT: u16 = 7777;
+// controls are sent this many frames before they are used, which hides the
+// time they take to arrive
+const INPUT_DELAY: u32 = 4;
+// the other end can only be INPUT_DELAY frames ahead of us, as it needs our
+// controls to move on, and its controls are INPUT_DELAY ahead of that
+const MAX_CONTROLS_AHEAD: u64 = 2 * INPUT_DELAY as u64 + 2;
+const HASH_INTERVAL: u32 = 60;
+const SILENCE_TIMEOUT: Duration = Duration::from_secs(3);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
+
+enum Role {
+    Host(TcpListener),
+    Join(SocketAddr),
+}
+
+enum SessionState {
+    Connecting,
+    Running,
+    Reconnecting(Instant),
+    Finished,
+}
+
+pub struct NetSession {
+    role: Role,
+    state: SessionState,
+    stream: Option<TcpStream>,
+    received: Vec<u8>,
+    sending: Vec<u8>,
+    last_heard: Instant,
+    last_progress: Instant,
+    last_attempt: Option<Instant>,
+    // the host chooses the seed and options for both games
+    seed: u64,
+    options: String,
+    local_player: usize,
+    frame: u32,
+    local_controls: Vec<u8>,
+    remote_controls: Vec<Option<u8>>,
+    local_hashes: HashMap<u32, u64>,
+    remote_hashes: HashMap<u32, u64>,
+    peer_finished: bool,
+    desync: bool,
+    frame_limit: Option<u32>,
+}
+
+impl NetSession {
+    fn new(role: Role, local_player: usize, options: String) -> NetSession {
+        NetSession {
+            role,
+            state: SessionState::Connecting,
+            stream: None,
+            received: Vec::new(),
+            sending: Vec::new(),
+            last_heard: Instant::now(),
+            last_progress: Instant::now(),
+            last_attempt: None,
+            seed: rand::random(),
+            options,
+            local_player,
+            frame: 0,
+            local_controls: Vec::new(),
+            remote_controls: Vec::new(),
+            local_hashes: HashMap::new(),
+            remote_hashes: HashMap::new(),
+            peer_finished: false,
+            desync: false,
+            frame_limit: None,
+        }
+    }
+
+    // the host plays the first ship, or the ship in versus
+    pub fn host(addr: &str, options: &str) -> Result<NetSession, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("Can't listen on {}: {}", addr, e))?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        // options have to fit on one line of the protocol
+        Ok(NetSession::new(Role::Host(listener), 0, options.replace('\n', ";")))
+    }
+
+    pub fn join(addr: &str) -> Result<NetSession, String> {
+        let addr = addr.to_socket_addrs().ok().and_then(|mut a| a.next())
+            .ok_or(format!("Can't find {}", addr))?;
+        Ok(NetSession::new(Role::Join(addr), 1, String::new()))
+    }
+
+    #[cfg(test)]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self.role {
+            Role::Host(ref listener) => {listener.local_addr().ok()},
+            Role::Join(_) => {None},
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        matches!(self.state, SessionState::Finished)
+    }
+
+    fn connect(&mut self, game: &mut Game) {
+        let connecting = matches!(self.state, SessionState::Connecting);
+        let stream = match self.role {
+            Role::Host(ref listener) => {
+                if connecting {
+                    game.set_network_status(Some("Waiting for the other player"));
+                }
+                listener.accept().ok().map(|(stream, _)| stream)
+            },
+            Role::Join(addr) => {
+                if connecting {
+                    game.set_network_status(Some("Connecting to the other player"));
+                }
+                if self.last_attempt.is_some_and(|t| t.elapsed() < RECONNECT_INTERVAL) {
+                    return;
+                }
+                self.last_attempt = Some(Instant::now());
+                TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()
+            },
+        };
+        if let Some(stream) = stream {
+            if stream.set_nonblocking(true).is_err() {
+                return;
+            }
+            let _res = stream.set_nodelay(true);
+            self.stream = Some(stream);
+            self.received.clear();
+            self.sending.clear();
+            self.last_heard = Instant::now();
+            match self.state {
+                SessionState::Connecting => {
+                    if let Role::Host(_) = self.role {
+                        let hello = format!("HELLO {} {} {}\n", PROTOCOL_VERSION, self.seed, self.options);
+                        self.send(&hello);
+                    }
+                },
+                SessionState::Reconnecting(_) => {
+                    // ask for everything from the frame we are stuck on
+                    self.send(&format!("RESUME {}\n", self.frame));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn lost_connection(&mut self, game: &mut Game) {
+        if let Some(stream) = self.stream.take() {
+            let _res = stream.shutdown(Shutdown::Both);
+        }
+        self.sending.clear();
+        // once the other end has finished we should have all we need
+        if self.peer_finished {
+            return;
+        }
+        if let SessionState::Running = self.state {
+            self.state = SessionState::Reconnecting(Instant::now());
+            game.set_network_status(Some("Connection lost, reconnecting"));
+        }
+    }
+
+    fn abandon(&mut self, game: &mut Game, status: &'static str) {
+        self.finish(game, Some(status));
+    }
+
+    fn finish(&mut self, game: &mut Game, status: Option<&'static str>) {
+        if let Some(stream) = self.stream.take() {
+            // give anything still to send a moment to go
+            let _res = stream.set_nonblocking(false);
+            let _res = stream.set_write_timeout(Some(SILENCE_TIMEOUT));
+            let _res = (&stream).write_all(&self.sending);
+            let _res = stream.shutdown(Shutdown::Write);
+        }
+        self.state = SessionState::Finished;
+        game.end_network_game(status);
+    }
+
+    fn send(&mut self, message: &str) {
+        if self.stream.is_some() {
+            self.sending.extend_from_slice(message.as_bytes());
+        }
+    }
+
+    fn flush(&mut self, game: &mut Game) {
+        let mut lost = false;
+        if let Some(ref mut stream) = self.stream {
+            while ! self.sending.is_empty() {
+                match stream.write(&self.sending) {
+                    Ok(0) => {lost = true; break;},
+                    Ok(n) => {self.sending.drain(..n);},
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {break;},
+                    Err(_) => {lost = true; break;},
+                }
+            }
+        }
+        if lost {
+            self.lost_connection(game);
+        }
+    }
+
+    fn receive(&mut self, game: &mut Game) {
+        let mut lost = false;
+        if let Some(ref mut stream) = self.stream {
+            let mut buf = [0; 4096];
+            loop {
+                match stream.read(&mut buf) {
+                    Ok(0) => {lost = true; break;},
+                    Ok(n) => {
+                        self.received.extend_from_slice(&buf[..n]);
+                        self.last_heard = Instant::now();
+                    },
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {break;},
+                    Err(_) => {lost = true; break;},
+                }
+            }
+        }
+        while let Some(end) = self.received.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.received.drain(..end + 1).collect();
+            let line = String::from_utf8_lossy(&line[..end]).into_owned();
+            self.handle_message(game, &line);
+            if self.finished() {
+                return;
+            }
+        }
+        if lost {
+            self.lost_connection(game);
+        }
+    }
+
+    fn start(&mut self, game: &mut Game) {
+        game.start_network_game(self.seed, &self.options, self.local_player);
+        // the first few frames are played with no controls while the
+        // first real ones are on their way
+        self.local_controls = vec![0; INPUT_DELAY as usize];
+        self.remote_controls = vec![Some(0); INPUT_DELAY as usize];
+        self.state = SessionState::Running;
+    }
+
+    fn handle_message(&mut self, game: &mut Game, line: &str) {
+        let words: Vec<&str> = line.splitn(4, ' ').collect();
+        let number = |i: usize| words.get(i).and_then(|w| w.parse::<u64>().ok());
+        match words[0] {
+            "HELLO" => {
+                if number(1) != Some(PROTOCOL_VERSION as u64) {
+                    self.abandon(game, "The other player has a different version");
+                    return;
+                }
+                if let (Some(seed), true) = (number(2), matches!(self.state, SessionState::Connecting)) {
+                    self.seed = seed;
+                    self.options = words.get(3).unwrap_or(&"").to_string();
+                    self.start(game);
+                    self.send("READY\n");
+                }
+            },
+            "READY" => {
+                if let SessionState::Connecting = self.state {
+                    self.start(game);
+                }
+            },
+            "I" => {
+                if let (Some(frame), Some(controls)) = (number(1), number(2)) {
+                    // anything further ahead than the other end could be is
+                    // not to be trusted with the size of remote_controls
+                    if frame > self.frame as u64 + MAX_CONTROLS_AHEAD {
+                        self.abandon(game, "Out of step, game abandoned");
+                        return;
+                    }
+                    // controls for frames already played are resent after
+                    // a reconnection, we have them already
+                    if frame < self.frame as u64 {
+                        return;
+                    }
+                    let frame = frame as usize;
+                    if self.remote_controls.len() <= frame {
+                        self.remote_controls.resize(frame + 1, None);
+                    }
+                    self.remote_controls[frame] = Some(controls as u8);
+                }
+            },
+            "H" => {
+                if let (Some(frame), Some(hash)) = (number(1), number(2)) {
+                    self.remote_hashes.insert(frame as u32, hash);
+                    self.check_hash(game, frame as u32);
+                }
+            },
+            "RESUME" => {
+                if let Some(frame) = number(1) {
+                    for f in frame as usize..self.local_controls.len() {
+                        let message = format!("I {} {}\n", f, self.local_controls[f]);
+                        self.send(&message);
+                    }
+                    // hashes the other end has not matched may have been lost
+                    // with the connection too
+                    let mut hashes: Vec<(u32, u64)> = self.local_hashes.iter().map(|(&f, &h)| (f, h)).collect();
+                    hashes.sort();
+                    for (f, hash) in hashes {
+                        let message = format!("H {} {}\n", f, hash);
+                        self.send(&message);
+                    }
+                    if let SessionState::Reconnecting(_) = self.state {
+                        self.state = SessionState::Running;
+                    }
+                    game.set_network_status(None);
+                }
+            },
+            "BYE" => {
+                self.peer_finished = true;
+            },
+            "DESYNC" => {
+                self.desync = true;
+                self.abandon(game, "Out of step, game abandoned");
+            },
+            _ => {},
+        }
+    }
+
+    fn check_hash(&mut self, game: &mut Game, frame: u32) {
+        if let (Some(local), Some(remote)) = (self.local_hashes.get(&frame), self.remote_hashes.get(&frame)) {
+            if local != remote {
+                self.desync = true;
+                self.send("DESYNC\n");
+                self.abandon(game, "Out of step, game abandoned");
+                return;
+            }
+            self.local_hashes.remove(&frame);
+            self.remote_hashes.remove(&frame);
+        }
+    }
+
+    // called once per frame in place of Game::update, the game only moves on
+    // when the controls from both ends have arrived
+    pub fn tick<F: FnMut(&mut Game) -> u8>(&mut self, game: &mut Game, mut local_controls: F) {
+        if self.finished() {
+            return;
+        }
+        if self.stream.is_none() && ! self.peer_finished {
+            self.connect(game);
+        }
+        self.receive(game);
+        if self.stream.is_some() && self.last_heard.elapsed() > SILENCE_TIMEOUT {
+            self.lost_connection(game);
+        }
+        if let SessionState::Reconnecting(since) = self.state {
+            if since.elapsed() > RECONNECT_TIMEOUT {
+                self.abandon(game, "Connection lost, game abandoned");
+            }
+        }
+        if self.peer_finished && self.stream.is_none() && ! self.finished() &&
+            self.last_progress.elapsed() > RECONNECT_TIMEOUT {
+            self.abandon(game, "Connection lost, game abandoned");
+        }
+        if let SessionState::Running = self.state {
+            if self.local_controls.len() as u32 <= self.frame + INPUT_DELAY {
+                let frame = self.local_controls.len();
+                let controls = local_controls(game);
+                self.local_controls.push(controls);
+                self.send(&format!("I {} {}\n", frame, controls));
+            }
+            let limit_reached = self.frame_limit.is_some_and(|limit| self.frame >= limit);
+            let remote = self.remote_controls.get(self.frame as usize).copied().flatten();
+            if let (Some(remote), false) = (remote, limit_reached) {
+                let mut controls = [0; 2];
+                controls[self.local_player] = self.local_controls[self.frame as usize];
+                controls[1 - self.local_player] = remote;
+                game.set_network_controls(controls);
+                game.update();
+                self.frame += 1;
+                self.last_progress = Instant::now();
+                if self.frame.is_multiple_of(HASH_INTERVAL) {
+                    let hash = game.state_hash();
+                    self.local_hashes.insert(self.frame, hash);
+                    self.send(&format!("H {} {}\n", self.frame, hash));
+                    self.check_hash(game, self.frame);
+                }
+                if game.network_game_over() && ! self.finished() {
+                    self.send("BYE\n");
+                    self.finish(game, None);
+                    return;
+                }
+            }
+        }
+        self.flush(game);
+    }
+}
+
+// --host [address] or --join address on the command line start a network game
+pub fn session_from_args(args: &[String], game: &Game) -> Option<NetSession> {
+    let position = args.iter().position(|a| a == "--host" || a == "--join")?;
+    let addr = args.get(position + 1).filter(|a| ! a.starts_with("--"));
+    let session = if args[position] == "--host" {
+        let default_addr = format!("0.0.0.0:{}", DEFAULT_PORT);
+        NetSession::host(addr.unwrap_or(&default_addr), &game.options_contents())
+    }
+    else {
+        match addr {
+            Some(addr) => {NetSession::join(addr)},
+            None => {Err(String::from("--join needs an address"))},
+        }
+    };
+    match session {
+        Ok(session) => {Some(session)},
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+    use std::time::{Duration, Instant};
+    use rand::Rng;
+    use sdl2::mixer::{AUDIO_S16LSB, DEFAULT_CHANNELS};
+    use sdl2::render::TextureCreator;
+    use sdl2::video::WindowContext;
+    use sdl2::ttf::Sdl2TtfContext;
+
+    use super::*;
+    use crate::common;
+    use crate::game_mode::GameMode;
+
+    const LOOPBACK_FRAMES: u32 = 3000;
+    const LOOPBACK_TIME_LIMIT: Duration = Duration::from_secs(120);
+
+    // two games played against each other over loopback with random
+    // controls, dropping the connection part way through, should finish in
+    // step
+    #[test]
+    fn loopback_games_stay_in_step() {
+        // keep away from the real options and high scores
+        let config_dir = std::env::temp_dir().join(format!("fin-sdl-test-{}", std::process::id()));
+        std::env::set_var("XDG_CONFIG_HOME", &config_dir);
+        // no display or sound card is needed
+        sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+        sdl2::hint::set("SDL_AUDIODRIVER", "dummy");
+        let sdl_context = sdl2::init().unwrap();
+        let video_subsys = sdl_context.video().unwrap();
+        let window = video_subsys.window("FIN (sdl)", 600, 700).build().unwrap();
+        let canvas = window.into_canvas().build().unwrap();
+        let texture_creator = canvas.texture_creator();
+        let ttf_context = sdl2::ttf::init().unwrap();
+        let _audio = sdl_context.audio().unwrap();
+        sdl2::mixer::open_audio(44_100, AUDIO_S16LSB, DEFAULT_CHANNELS, 1_024).unwrap();
+
+        for game_mode in [GameMode::Cooperative, GameMode::Versus] {
+            loopback_run(&texture_creator, &ttf_context, game_mode);
+        }
+        let _res = std::fs::remove_dir_all(&config_dir);
+    }
+
+    fn loopback_run(texture_creator: &TextureCreator<WindowContext>, ttf_context: &Sdl2TtfContext,
+        game_mode: GameMode) {
+        let mut host_game = Game::new(texture_creator, ttf_context);
+        let mut join_game = Game::new(texture_creator, ttf_context);
+        let options = format!("{}game_mode={}", host_game.options_contents(), game_mode.name());
+        let mut host = NetSession::host("127.0.0.1:0", &options).unwrap();
+        let addr = host.local_addr().unwrap().to_string();
+        let mut join = NetSession::join(&addr).unwrap();
+        host.frame_limit = Some(LOOPBACK_FRAMES);
+        join.frame_limit = Some(LOOPBACK_FRAMES);
+        let mut host_rng = common::new_random(1);
+        let mut join_rng = common::new_random(2);
+        let mut dropped = false;
+
+        let started = Instant::now();
+        while started.elapsed() < LOOPBACK_TIME_LIMIT {
+            host.tick(&mut host_game, |_| host_rng.gen_range(0..16));
+            join.tick(&mut join_game, |_| join_rng.gen_range(0..16));
+            if ! dropped && join.frame == LOOPBACK_FRAMES / 2 {
+                join.lost_connection(&mut join_game);
+                dropped = true;
+            }
+            let done = |s: &NetSession| s.finished() || s.frame == LOOPBACK_FRAMES;
+            if done(&host) && done(&join) {
+                break;
+            }
+            sleep(Duration::from_millis(1));
+        }
+
+        assert!(! host.desync && ! join.desync, "{} games got out of step", game_mode.name());
+        assert_eq!(host.frame, join.frame, "{} games stopped on different frames", game_mode.name());
+        assert_eq!(host_game.state_hash(), join_game.state_hash(), "{} games finished out of step", game_mode.name());
+    }
+}
@@ -0,0 +1,142 @@
+// player options, these persist between runs
+
+use std::fs;
+
+use crate::common;
+use crate::collision::CollisionMode;
+use crate::difficulty::Difficulty;
+use crate::game_mode::GameMode;
+use crate::rules::Rules;
+
+const OPTIONS_FILE: &str = "options.cfg";
+
+#[derive(Clone)]
+pub struct Options {
+    pub game_mode: GameMode,
+    pub rules: Rules,
+    pub difficulty: Difficulty,
+    // whether the missile can shoot down spider bombs, for each difficulty
+    pub shoot_bombs: [bool; 3],
+    // whether co-op ships draw on one pool of lives
+    pub shared_lives: bool,
+    pub screen_shake: bool,
+    pub hit_stop: bool,
+    pub flashes: bool,
+    // these are only set by editing the options file
+    pub spider_collision: CollisionMode,
+    pub bonus_bomb_collision: CollisionMode,
+    pub ship_collision: CollisionMode,
+    // whether a wounded armoured spider lets go of the brick it carries
+    pub wounded_drop_brick: bool,
+    // whether the base bricks stop spider bombs, losing a brick each time
+    pub brick_cover: bool,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            game_mode: GameMode::Single,
+            rules: Rules::Classic,
+            difficulty: Difficulty::Normal,
+            shoot_bombs: [true, false, false],
+            shared_lives: false,
+            screen_shake: true,
+            hit_stop: true,
+            flashes: true,
+            spider_collision: CollisionMode::Aabb,
+            bonus_bomb_collision: CollisionMode::Aabb,
+            ship_collision: CollisionMode::Aabb,
+            wounded_drop_brick: true,
+            brick_cover: false,
+        }
+    }
+
+    pub fn load() -> Options {
+        match common::config_path(OPTIONS_FILE).and_then(|path| fs::read_to_string(path).ok()) {
+            Some(contents) => {Options::from_contents(&contents)},
+            None => {Options::new()},
+        }
+    }
+
+    // settings are one per line as key=value, but a ; will do instead of a
+    // new line when they need to fit on one line
+    pub fn from_contents(contents: &str) -> Options {
+        let mut options = Options::new();
+        for line in contents.split(['\n', ';']) {
+            if let Some((key, value)) = line.split_once('=') {
+                options.set(key.trim(), value.trim());
+            }
+        }
+        options
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        let on = value == "on";
+        match key {
+            "game_mode" => {
+                self.game_mode = GameMode::from_name(value).unwrap_or(self.game_mode);
+            },
+            "rules" => {
+                self.rules = Rules::from_name(value).unwrap_or(self.rules);
+            },
+            "difficulty" => {
+                self.difficulty = Difficulty::from_name(value).unwrap_or(self.difficulty);
+            },
+            "shoot_bombs_easy" => {self.shoot_bombs[Difficulty::Easy as usize] = on;},
+            "shoot_bombs_normal" => {self.shoot_bombs[Difficulty::Normal as usize] = on;},
+            "shoot_bombs_hard" => {self.shoot_bombs[Difficulty::Hard as usize] = on;},
+            "shared_lives" => {self.shared_lives = on;},
+            "screen_shake" => {self.screen_shake = on;},
+            "hit_stop" => {self.hit_stop = on;},
+            "flashes" => {self.flashes = on;},
+            "spider_collision" => {
+                self.spider_collision = CollisionMode::from_name(value).unwrap_or(self.spider_collision);
+            },
+            "bonus_bomb_collision" => {
+                self.bonus_bomb_collision = CollisionMode::from_name(value).unwrap_or(self.bonus_bomb_collision);
+            },
+            "ship_collision" => {
+                self.ship_collision = CollisionMode::from_name(value).unwrap_or(self.ship_collision);
+            },
+            "wounded_drop_brick" => {self.wounded_drop_brick = on;},
+            "brick_cover" => {self.brick_cover = on;},
+            _ => {},
+        }
+    }
+
+    pub fn shoot_bombs(&self) -> bool {
+        self.shoot_bombs[self.difficulty as usize]
+    }
+
+    pub fn contents(&self) -> String {
+        let on_off = |b: bool| if b {"on"} else {"off"};
+        let settings = [
+            ("game_mode", self.game_mode.name()),
+            ("rules", self.rules.name()),
+            ("difficulty", self.difficulty.name()),
+            ("shoot_bombs_easy", on_off(self.shoot_bombs[Difficulty::Easy as usize])),
+            ("shoot_bombs_normal", on_off(self.shoot_bombs[Difficulty::Normal as usize])),
+            ("shoot_bombs_hard", on_off(self.shoot_bombs[Difficulty::Hard as usize])),
+            ("shared_lives", on_off(self.shared_lives)),
+            ("screen_shake", on_off(self.screen_shake)),
+            ("hit_stop", on_off(self.hit_stop)),
+            ("flashes", on_off(self.flashes)),
+            ("spider_collision", self.spider_collision.name()),
+            ("bonus_bomb_collision", self.bonus_bomb_collision.name()),
+            ("ship_collision", self.ship_collision.name()),
+            ("wounded_drop_brick", on_off(self.wounded_drop_brick)),
+            ("brick_cover", on_off(self.brick_cover)),
+        ];
+        settings.iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect()
+    }
+
+    pub fn save(&self) {
+        let contents = self.contents();
+        // not being able to save options is no reason to stop the game
+        if let Some(path) = common::config_path(OPTIONS_FILE) {
+            let _res = fs::write(path, contents);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+// scripted flight paths and the formations that fly them. A path is made of
+// segments, each starting where the last one ended and taking a set number
+// of frames. Points are relative to where the path starts, with x towards
+// the middle of the screen so that a path can be flown from either side.
+// Which formations an attack starts with is set in attacks.rs
+
+use std::f64::consts::PI;
+
+#[derive(Copy, Clone)]
+pub enum Segment {
+    // a cubic Bézier curve through two control points to the end point
+    Bezier((f64, f64), (f64, f64), (f64, f64), u32),
+    // a Catmull-Rom spline through each of the points
+    Spline(&'static [(f64, f64)], u32),
+    // a circle around a centre relative to the start of the segment, the
+    // number of turns is negative for anticlockwise
+    Loop((f64, f64), f64, u32),
+}
+
+// the names are only shown in the debug overlay preview
+#[cfg_attr(not(feature = "debug-overlay"), allow(dead_code))]
+pub struct Path {
+    pub name: &'static str,
+    pub segments: &'static [Segment],
+}
+
+// each slot is how far along and across from the leader, which flies the
+// path itself, a follower keeps, turning as the leader turns
+#[cfg_attr(not(feature = "debug-overlay"), allow(dead_code))]
+pub struct Formation {
+    pub name: &'static str,
+    pub path: usize,
+    pub slots: &'static [(f64, f64)],
+}
+
+pub const PATHS: [Path; 3] = [
+    Path {name: "swoop and loop", segments: &[
+        Segment::Bezier((60.0, 0.0), (120.0, 60.0), (120.0, 160.0), 50),
+        Segment::Loop((-60.0, 0.0), 1.0, 80),
+        Segment::Bezier((120.0, 220.0), (80.0, 280.0), (40.0, 320.0), 40),
+    ]},
+    Path {name: "s-curve", segments: &[
+        Segment::Spline(&[(80.0, 60.0), (20.0, 140.0), (100.0, 220.0), (180.0, 290.0)], 150),
+    ]},
+    Path {name: "sweep", segments: &[
+        Segment::Bezier((180.0, -20.0), (260.0, 120.0), (160.0, 200.0), 70),
+        Segment::Spline(&[(60.0, 240.0), (0.0, 300.0), (40.0, 340.0)], 60),
+    ]},
+];
+
+pub const FORMATIONS: [Formation; 3] = [
+    Formation {name: "vee", path: 0, slots: &[
+        (0.0, 0.0), (-30.0, -30.0), (-30.0, 30.0), (-60.0, -60.0), (-60.0, 60.0)]},
+    Formation {name: "line", path: 1, slots: &[
+        (0.0, 0.0), (-35.0, 0.0), (-70.0, 0.0), (-105.0, 0.0)]},
+    Formation {name: "pair", path: 2, slots: &[
+        (0.0, -20.0), (0.0, 20.0)]},
+];
+
+impl Segment {
+    fn frames(&self) -> u32 {
+        match *self {
+            Segment::Bezier(_, _, _, frames) => {frames},
+            Segment::Spline(_, frames) => {frames},
+            Segment::Loop(_, _, frames) => {frames},
+        }
+    }
+
+    // t runs from 0 at the start of the segment to 1 at the end
+    fn position(&self, start: (f64, f64), t: f64) -> (f64, f64) {
+        match *self {
+            Segment::Bezier(c1, c2, end, _) => {
+                let u = 1.0 - t;
+                let blend = |p0: f64, p1: f64, p2: f64, p3: f64|
+                    u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3;
+                (blend(start.0, c1.0, c2.0, end.0), blend(start.1, c1.1, c2.1, end.1))
+            },
+            Segment::Spline(points, _) => {
+                // the ends are repeated so the curve goes through every point
+                let point = |i: isize| {
+                    let i = i.clamp(0, points.len() as isize);
+                    if i == 0 {start} else {points[i as usize - 1]}
+                };
+                let spans = points.len() as f64;
+                let i = ((t * spans) as isize).min(points.len() as isize - 1);
+                let t = t * spans - i as f64;
+                let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+                let blend = |p0: f64, p1: f64, p2: f64, p3: f64|
+                    0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t +
+                           (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+                (blend(p0.0, p1.0, p2.0, p3.0), blend(p0.1, p1.1, p2.1, p3.1))
+            },
+            Segment::Loop((dx, dy), turns, _) => {
+                let (centre_x, centre_y) = (start.0 + dx, start.1 + dy);
+                let radius = dx.hypot(dy);
+                let angle = (-dy).atan2(-dx) + 2.0 * PI * turns * t;
+                (centre_x + radius * angle.cos(), centre_y + radius * angle.sin())
+            },
+        }
+    }
+}
+
+impl Path {
+    pub fn frames(&self) -> u32 {
+        self.segments.iter().map(|segment| segment.frames()).sum()
+    }
+
+    pub fn position(&self, frame: u32) -> (f64, f64) {
+        let mut start = (0.0, 0.0);
+        let mut frame = frame;
+        for segment in self.segments {
+            let frames = segment.frames();
+            if frame < frames {
+                return segment.position(start, frame as f64 / frames as f64);
+            }
+            frame -= frames;
+            start = segment.position(start, 1.0);
+        }
+        start
+    }
+
+    // the direction of travel in radians, looking back from the end
+    pub fn heading(&self, frame: u32) -> f64 {
+        let frame = frame.min(self.frames().max(1) - 1);
+        let (x0, y0) = self.position(frame);
+        let (x1, y1) = self.position(frame + 1);
+        (y1 - y0).atan2(x1 - x0)
+    }
+}
+
+impl Formation {
+    pub fn path(&self) -> &'static Path {
+        &PATHS[self.path]
+    }
+
+    pub fn position(&self, frame: u32, slot: usize) -> (f64, f64) {
+        let path = self.path();
+        let (x, y) = path.position(frame);
+        let heading = path.heading(frame);
+        let (along, across) = self.slots[slot];
+        (x + along * heading.cos() - across * heading.sin(),
+         y + along * heading.sin() + across * heading.cos())
+    }
+}
@@ -0,0 +1,136 @@
+// timed power-ups granted by shooting a power-up bonus bomb, shared by
+// every ship on screen
+
+use std::hash::{Hash, Hasher};
+use sdl2::video::{Window, WindowContext};
+use sdl2::render::{Canvas, TextureCreator};
+
+use crate::common::{ScreenObjectArea, SCREEN_WIDTH, UPDATE_FPS};
+use crate::image::Image;
+use crate::text::Text;
+use crate::spectator;
+use crate::soundfx::SoundEffect;
+
+pub const POWER_UPS: usize = 5;
+const DURATION_FRAMES: [u32; POWER_UPS] =
+    [15 * UPDATE_FPS, 10 * UPDATE_FPS, 12 * UPDATE_FPS, 20 * UPDATE_FPS, 8 * UPDATE_FPS];
+// a power-up about to run out flickers for this long
+const WARNING_FRAMES: u32 = 2 * UPDATE_FPS;
+const HUD_X: i32 = SCREEN_WIDTH as i32 - 110;
+const HUD_Y: i32 = 45;
+const HUD_SPACING: i32 = 26;
+const ICON_SCALE: f64 = 0.6;
+const SHIELD_WIDTH: u32 = 44;
+const SHIELD_HEIGHT: u32 = 54;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum PowerUp {
+    DoubleMissile,
+    RapidFire,
+    Piercing,
+    Shield,
+    SlowDown,
+}
+
+pub struct PowerUps<'a> {
+    frames_left: [u32; POWER_UPS],
+    icon_image: [Image<'a>; POWER_UPS],
+    shield_image: Image<'a>,
+    grant_sound: [SoundEffect; POWER_UPS],
+    shield_hit_sound: SoundEffect,
+}
+
+impl<'a> PowerUps<'a> {
+    pub fn new(texture_creator: &TextureCreator<WindowContext>) -> PowerUps<'_> {
+        PowerUps {
+            frames_left: [0; POWER_UPS],
+            icon_image: [Image::new(texture_creator, "powerup_double_1.png", 50, 22),
+                         Image::new(texture_creator, "powerup_rapid_1.png", 50, 22),
+                         Image::new(texture_creator, "powerup_pierce_1.png", 50, 22),
+                         Image::new(texture_creator, "powerup_shield_1.png", 50, 22),
+                         Image::new(texture_creator, "powerup_slow_1.png", 50, 22)],
+            shield_image: Image::new(texture_creator, "shield.png", SHIELD_WIDTH, SHIELD_HEIGHT),
+            grant_sound: [SoundEffect::new("powerup_double.wav"),
+                          SoundEffect::new("powerup_rapid.wav"),
+                          SoundEffect::new("powerup_pierce.wav"),
+                          SoundEffect::new("powerup_shield.wav"),
+                          SoundEffect::new("powerup_slow.wav")],
+            shield_hit_sound: SoundEffect::new("shield_hit.wav"),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.frames_left = [0; POWER_UPS];
+    }
+
+    // collecting one already running starts its timer again
+    pub fn grant(&mut self, power_up: PowerUp) {
+        self.frames_left[power_up as usize] = DURATION_FRAMES[power_up as usize];
+        self.grant_sound[power_up as usize].play();
+    }
+
+    pub fn active(&self, power_up: PowerUp) -> bool {
+        self.frames_left[power_up as usize] > 0
+    }
+
+    // the shield is gone once it has stopped a bomb
+    pub fn use_shield(&mut self) -> bool {
+        if self.active(PowerUp::Shield) {
+            self.frames_left[PowerUp::Shield as usize] = 0;
+            self.shield_hit_sound.play();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    pub fn update(&mut self) {
+        for frames in self.frames_left.iter_mut().filter(|f| **f > 0) {
+            *frames -= 1;
+        }
+    }
+
+    fn showing(&self, p: usize, frame_count: u32) -> bool {
+        let frames = self.frames_left[p];
+        frames > WARNING_FRAMES || (frames > 0 && (frame_count / 8).is_multiple_of(2))
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        self.frames_left.hash(state);
+    }
+
+    pub fn describe(&self) -> String {
+        let frames: Vec<String> = self.frames_left.iter().map(|f| f.to_string()).collect();
+        frames.join(":")
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        for (p, frames) in self.frames_left.iter_mut().enumerate() {
+            *frames = spectator::field(&fields, p, 0);
+        }
+    }
+
+    pub fn render_shield(&self, canvas: &mut Canvas<Window>, ship_area: &ScreenObjectArea, frame_count: u32) {
+        if self.showing(PowerUp::Shield as usize, frame_count) {
+            let (x, y) = ship_area.position();
+            let (w, h) = ship_area.size();
+            self.shield_image.render(canvas, x + (w as i32 - SHIELD_WIDTH as i32) / 2,
+                y + (h as i32 - SHIELD_HEIGHT as i32) / 2);
+        }
+    }
+
+    // an icon and the seconds left for each running power-up
+    pub fn render_hud(&self, canvas: &mut Canvas<Window>, text: &Text, frame_count: u32) {
+        let mut y = HUD_Y;
+        for p in (0..POWER_UPS).filter(|&p| self.frames_left[p] > 0) {
+            if self.showing(p, frame_count) {
+                self.icon_image[p].render_resize(canvas, HUD_X, y + 6, ICON_SCALE);
+                let seconds = self.frames_left[p].div_ceil(UPDATE_FPS);
+                text.render(canvas, HUD_X + 38, y, 79, 120, 181, 255, &format!("{}s", seconds));
+            }
+            y += HUD_SPACING;
+        }
+    }
+}
@@ -0,0 +1,46 @@
+// what ends a game and what finishing the word costs
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Rules {
+    Classic,
+    Survival,
+    TimeAttack,
+}
+
+impl Rules {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Rules::Classic => {"Classic"},
+            Rules::Survival => {"Survival"},
+            Rules::TimeAttack => {"Time attack"},
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rules> {
+        match name {
+            "Classic" => {Some(Rules::Classic)},
+            "Survival" => {Some(Rules::Survival)},
+            "Time attack" => {Some(Rules::TimeAttack)},
+            _ => {None},
+        }
+    }
+
+    pub fn next(&self) -> Rules {
+        match *self {
+            Rules::Classic => {Rules::Survival},
+            Rules::Survival => {Rules::TimeAttack},
+            Rules::TimeAttack => {Rules::Classic},
+        }
+    }
+
+    // whether attacks keep getting harder after the last one instead of
+    // repeating it
+    pub fn escalating(&self) -> bool {
+        matches!(self, Rules::Survival)
+    }
+
+    // whether shot spiders go back to the nest while the clock runs
+    pub fn respawning(&self) -> bool {
+        matches!(self, Rules::TimeAttack)
+    }
+}
@@ -1,12 +1,17 @@
+use std::hash::{Hash, Hasher};
 
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
 
 use crate::common::{SCREEN_WIDTH, SCREEN_HEIGHT, UPDATE_FPS, ScreenObjectArea};
 use crate::missile::Missile;
+use crate::power_ups::{PowerUp, PowerUps};
 use crate::image::Image;
+use crate::spectator;
 use crate::soundfx::SoundEffect;
 use crate::animation::{AnimationSeq, Animation, Animations};
+use crate::collision::{CollisionMode, CollisionMask, Hitbox};
+use crate::effects::ScreenEffects;
 
 const SHIP_WIDTH: u32 = 30;
 const SHIP_HEIGHT: u32 = 40;
@@ -15,8 +20,13 @@ const SHIP_SPEED: i32 = 5;
 const LIVES: u32 = 4;
 const LIVES_Y: i32 = (SCREEN_HEIGHT - 2 - SHIP_HEIGHT / 2) as i32;
 const LIVES_X: i32 = (SCREEN_WIDTH - 2 - SHIP_WIDTH / 2) as i32;
+// a second ship shows its lives from the middle of the screen outwards
+const SECOND_LIVES_X: i32 = (SCREEN_WIDTH / 2) as i32;
 const MIN_FRAMES_BEFORE_CHANGEOVER: u32 = UPDATE_FPS as u32 * 3 / 2;
 const GRACE_PERIOD_FRAMES: u32 = UPDATE_FPS as u32;
+const RAPID_FIRE_FRAMES: u32 = 8;
+const DOUBLE_MISSILE_SPREAD: i32 = 10;
+const PIERCING_HITS: u32 = 3;
 
 enum ShipState {
     Alive(u32),
@@ -25,34 +35,60 @@ enum ShipState {
 }
 
 pub struct Ship<'a> {
+    player: usize,
+    home_x: i32,
     x: i32,
+    prev_x: i32,
     state: ShipState,
     lives: u32,
+    reload: u32,
     ship_image: [Image<'a>; 3],
+    ship_mask: CollisionMask,
+    collision_mode: CollisionMode,
     ship_explosion_sound: SoundEffect,
     extra_life_sound: SoundEffect,
 }
 
 impl<'a> Ship<'a> {
-    pub fn new (texture_creator: &TextureCreator<WindowContext>) -> Ship {
-        Ship{x: Ship::home_x(),
+    pub fn new (texture_creator: &TextureCreator<WindowContext>, player: usize, collision_mode: CollisionMode) -> Ship {
+        let image_files = if player == 0
+            {["ship1.png", "ship2.png", "ship3.png"]}
+            else {["coop_ship1.png", "coop_ship2.png", "coop_ship3.png"]};
+        let home_x = ((SCREEN_WIDTH - SHIP_WIDTH) / 2) as i32;
+        Ship{player,
+             home_x,
+             x: home_x,
+             prev_x: home_x,
              state: ShipState::Alive(0),
              lives: LIVES,
-             ship_image: [Image::new(texture_creator, "ship1.png", SHIP_WIDTH, SHIP_HEIGHT),
-                          Image::new(texture_creator, "ship2.png", SHIP_WIDTH, SHIP_HEIGHT),
-                          Image::new(texture_creator, "ship3.png", SHIP_WIDTH, SHIP_HEIGHT)],
+             reload: 0,
+             ship_image: [Image::new(texture_creator, image_files[0], SHIP_WIDTH, SHIP_HEIGHT),
+                          Image::new(texture_creator, image_files[1], SHIP_WIDTH, SHIP_HEIGHT),
+                          Image::new(texture_creator, image_files[2], SHIP_WIDTH, SHIP_HEIGHT)],
+             ship_mask: CollisionMask::new(&image_files),
+             collision_mode,
              ship_explosion_sound: SoundEffect::new("ship_explosion.ogg"),
              extra_life_sound: SoundEffect::new("extra_life.ogg")}
     }
 
-    fn home_x() -> i32 {
-        ((SCREEN_WIDTH - SHIP_WIDTH) / 2) as i32
+    // position is the fraction of the way across the screen that the ship
+    // starts from and returns to
+    pub fn set_home(&mut self, position: f64) {
+        self.home_x = ((SCREEN_WIDTH - SHIP_WIDTH) as f64 * position) as i32;
+    }
+
+    // the stretch of screen a ship returning home will pass through, with a
+    // ship's width either side to spare
+    pub fn home_span(&self) -> (i32, i32) {
+        (self.home_x - SHIP_WIDTH as i32, self.home_x + 2 * SHIP_WIDTH as i32)
     }
 
     pub fn reset(&mut self) {
-        self.x = Ship::home_x();
+        self.x = self.home_x;
+        self.prev_x = self.x;
         self.state = ShipState::Alive(0);
         self.lives = LIVES;
+        self.reload = 0;
     }
 
     pub fn move_left(&mut self) {
@@ -71,17 +107,39 @@ impl<'a> Ship<'a> {
         }
     }
 
-    pub fn kill(&mut self, animations: &mut Animations) {
+    pub fn kill(&mut self, animations: &mut Animations, effects: &mut ScreenEffects) {
         if let ShipState::Alive(_) = self.state {
             let x = self.x;
             let animation = Animation::new(AnimationSeq::ShipExplosion(x, SHIP_Y));
             animations.register(animation);
+            effects.add_trauma(0.8);
+            effects.hit_stop(12);
+            effects.flash(255, 0, 0);
             self.ship_explosion_sound.play();
             self.state = ShipState::WaitForChangeOver(MIN_FRAMES_BEFORE_CHANGEOVER);
-            self.x = Ship::home_x();
+            self.x = self.home_x;
+            self.prev_x = self.x;
+        }
+    }
+
+    // the ship leaves without exploding, used when a player is out of the
+    // game for reasons other than losing their last life
+    pub fn stand_down(&mut self) {
+        if let ShipState::Alive(_) = self.state {
+            self.state = ShipState::WaitForChangeOver(MIN_FRAMES_BEFORE_CHANGEOVER);
+            self.x = self.home_x;
+            self.prev_x = self.x;
         }
     }
 
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    pub fn set_lives(&mut self, lives: u32) {
+        self.lives = lives;
+    }
+
     pub fn award_extra_life(&mut self, animations: &mut Animations) {
         self.lives += 1;
         let animation = Animation::new(AnimationSeq::ExtraLife);
@@ -102,11 +160,28 @@ impl<'a> Ship<'a> {
         return true;
     }
 
+    pub fn centre_x(&self) -> i32 {
+        self.x + (SHIP_WIDTH / 2) as i32
+    }
+
     pub fn area(&self) -> ScreenObjectArea {
         ScreenObjectArea::new(self.x, SHIP_Y, SHIP_WIDTH, SHIP_HEIGHT)
+            .moving(self.x - self.prev_x, 0)
+    }
+
+    pub fn hitbox(&self) -> Hitbox<'_> {
+        Hitbox::new(self.area()).shaped(self.collision_mode, &self.ship_mask)
+    }
+
+    pub fn set_collision_mode(&mut self, collision_mode: CollisionMode) {
+        self.collision_mode = collision_mode;
     }
 
     pub fn update(&mut self) {
+        self.prev_x = self.x;
+        if self.reload > 0 {
+            self.reload -= 1;
+        }
         match self.state {
             ShipState::WaitForChangeOver(n) => {
                 if n > 0 {
@@ -129,9 +204,22 @@ impl<'a> Ship<'a> {
         }
     }
 
-    pub fn launch_missile(&self, missile: &mut Missile) {
+    // normally there can only be one missile in flight, rapid fire allows
+    // another as soon as the ship has reloaded
+    pub fn launch_missiles(&mut self, missiles: &mut [Missile], power_ups: &PowerUps) {
         if let ShipState::Alive(_) = self.state {
-            missile.launch(self.x + (SHIP_WIDTH / 2) as i32, SHIP_Y);
+            let flying = missiles.iter().filter(|m| m.flying()).count();
+            let ready = if power_ups.active(PowerUp::RapidFire) {self.reload == 0} else {flying == 0};
+            let spread: &[i32] = if power_ups.active(PowerUp::DoubleMissile)
+                {&[-DOUBLE_MISSILE_SPREAD, DOUBLE_MISSILE_SPREAD]} else {&[0]};
+            let hits = if power_ups.active(PowerUp::Piercing) {PIERCING_HITS} else {1};
+            if ready && missiles.len() - flying >= spread.len() {
+                let x = self.x + (SHIP_WIDTH / 2) as i32;
+                for (missile, dx) in missiles.iter_mut().filter(|m| ! m.flying()).zip(spread) {
+                    missile.launch(x + dx, SHIP_Y, hits);
+                }
+                self.reload = RAPID_FIRE_FRAMES;
+            }
         }
     }
 
@@ -171,8 +259,48 @@ impl<'a> Ship<'a> {
         }
     }
 
-    fn life_x(life: u32) -> i32 {
-        LIVES_X - (life * (SHIP_WIDTH / 2 + 10)) as i32
+    fn life_x(&self, life: u32) -> i32 {
+        if self.player == 0 {
+            LIVES_X - (life * (SHIP_WIDTH / 2 + 10)) as i32
+        }
+        else {
+            SECOND_LIVES_X + (life * (SHIP_WIDTH / 2 + 10)) as i32
+        }
+    }
+
+    // everything that decides how the game plays out, for checking that two
+    // networked games are still in step
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        self.x.hash(state);
+        self.lives.hash(state);
+        self.reload.hash(state);
+        match self.state {
+            ShipState::Alive(n) => {(0, n).hash(state);},
+            ShipState::WaitForChangeOver(n) => {(1, n).hash(state);},
+            ShipState::ChangeOver(n) => {(2, n.to_bits()).hash(state);},
+        }
+    }
+
+    // for the spectator stream, see spectator.rs
+    pub fn describe(&self) -> String {
+        let (code, n) = match self.state {
+            ShipState::Alive(n) => {("A", n as f64)},
+            ShipState::WaitForChangeOver(n) => {("W", n as f64)},
+            ShipState::ChangeOver(n) => {("C", n)},
+        };
+        format!("{}:{}:{:.2}:{}", self.x, code, n, self.lives)
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        let fields: Vec<&str> = description.split(':').collect();
+        let n = spectator::field(&fields, 2, 0.0);
+        self.x = spectator::field(&fields, 0, self.x);
+        self.state = match fields.get(1) {
+            Some(&"W") => {ShipState::WaitForChangeOver(n as u32)},
+            Some(&"C") => {ShipState::ChangeOver(n)},
+            _ => {ShipState::Alive(n as u32)},
+        };
+        self.lives = spectator::field(&fields, 3, self.lives);
     }
 
     pub fn render(&self, canvas: &mut Canvas<Window>, frame_count: u32) {
@@ -182,12 +310,12 @@ impl<'a> Ship<'a> {
                 self.ship_image[(ship_pulse / 10) as usize].render(canvas, self.x, SHIP_Y);
             },
             ShipState::ChangeOver(n) => {
-                self.ship_image[0].render_resize(canvas, self.x + ((Ship::life_x(self.lives + 1) - self.x) as f64 * (1.0 - n)) as i32, SHIP_Y + ((LIVES_Y - SHIP_Y) as f64 * (1.0 - n)) as i32, 0.5 + 0.5 * n);
+                self.ship_image[0].render_resize(canvas, self.x + ((self.life_x(self.lives + 1) - self.x) as f64 * (1.0 - n)) as i32, SHIP_Y + ((LIVES_Y - SHIP_Y) as f64 * (1.0 - n)) as i32, 0.5 + 0.5 * n);
             },
             _ => {},
         }
         for i in 0..self.lives {
-            self.ship_image[0].render_resize(canvas, Ship::life_x(i), LIVES_Y, 0.5);
+            self.ship_image[0].render_resize(canvas, self.life_x(i), LIVES_Y, 0.5);
             
         }
     }
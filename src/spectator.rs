@@ -0,0 +1,161 @@
+// spectator stream - a line of text for every frame describing where
+// everything is, written to a file or to anyone connected to a socket, and
+// read back by `fin-sdl --spectate` to show the game without playing it
+//
+// a line is space separated key=value pairs, lists within a value are
+// separated by ; and the fields of an item by :
+//
+//   f=frame mode=mode st=state score=score screen=screen
+//   ships=x:state:n:lives;... missiles=x:y:hits;... (- when not flying)
+//   mother=x:y:pattern:n:weak... spiders=id:type:state:x:y:n:r;...
+//   bombs=x:y:kind:x velocity:y velocity;... bonus=x:y:type (- when not falling)
+//   power=frames:... (left for each power-up)
+//   base=x:filled:resupply:lost:cover letters=filled:expansion
+//
+// where filled is a hex string with a bit for each brick
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+
+// a watcher that falls this far behind is dropped
+const MAX_PENDING: usize = 64 * 1024;
+
+pub fn field<T: FromStr>(fields: &[&str], i: usize, default: T) -> T {
+    fields.get(i).and_then(|f| f.parse().ok()).unwrap_or(default)
+}
+
+pub fn list(items: &[String]) -> String {
+    if items.is_empty() {String::from("-")} else {items.join(";")}
+}
+
+pub fn items(description: &str) -> impl Iterator<Item = &str> {
+    description.split(';').filter(|&d| d != "-" && ! d.is_empty())
+}
+
+pub fn bits_to_hex(bits: &[bool]) -> String {
+    bits.chunks(4)
+        .map(|nibble| {
+            let n = nibble.iter().enumerate().fold(0, |n, (i, &b)| if b {n | 1 << i} else {n});
+            char::from_digit(n, 16).unwrap()
+        })
+        .collect()
+}
+
+pub fn hex_to_bits(hex: &str, bits: &mut [bool]) {
+    let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16).unwrap_or(0)).collect();
+    for (i, b) in bits.iter_mut().enumerate() {
+        *b = digits.get(i / 4).is_some_and(|n| n & (1 << (i % 4)) != 0);
+    }
+}
+
+pub struct Watcher {
+    stream: TcpStream,
+    pending: Vec<u8>,
+}
+
+pub enum SpectatorOutput {
+    File(BufWriter<File>),
+    Socket(TcpListener, Vec<Watcher>),
+}
+
+impl SpectatorOutput {
+    // tcp:address listens for watchers, anything else is a file to write
+    pub fn open(target: &str) -> Result<SpectatorOutput, String> {
+        match target.strip_prefix("tcp:") {
+            Some(addr) => {
+                let listener = TcpListener::bind(addr).map_err(|e| format!("Can't listen on {}: {}", addr, e))?;
+                listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+                Ok(SpectatorOutput::Socket(listener, Vec::new()))
+            },
+            None => {
+                let file = File::create(target).map_err(|e| format!("Can't create {}: {}", target, e))?;
+                Ok(SpectatorOutput::File(BufWriter::new(file)))
+            },
+        }
+    }
+
+    pub fn write_frame(&mut self, description: &str) {
+        match self {
+            SpectatorOutput::File(file) => {
+                let _res = writeln!(file, "{}", description).and_then(|_| file.flush());
+            },
+            SpectatorOutput::Socket(listener, watchers) => {
+                while let Ok((stream, _)) = listener.accept() {
+                    if stream.set_nonblocking(true).is_ok() {
+                        watchers.push(Watcher {stream, pending: Vec::new()});
+                    }
+                }
+                // a slow watcher must never hold up the game
+                watchers.retain_mut(|w| {
+                    w.pending.extend_from_slice(description.as_bytes());
+                    w.pending.push(b'\n');
+                    while ! w.pending.is_empty() {
+                        match w.stream.write(&w.pending) {
+                            Ok(0) => {return false;},
+                            Ok(n) => {w.pending.drain(..n);},
+                            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {break;},
+                            Err(_) => {return false;},
+                        }
+                    }
+                    w.pending.len() < MAX_PENDING
+                });
+            },
+        }
+    }
+}
+
+pub enum SpectatorInput {
+    // a file is shown a line a frame, as it was recorded
+    File(BufReader<File>, String),
+    // a live game is shown as it is now, skipping anything missed
+    Socket(TcpStream, Vec<u8>),
+}
+
+impl SpectatorInput {
+    pub fn open(source: &str) -> Result<SpectatorInput, String> {
+        match source.strip_prefix("tcp:") {
+            Some(addr) => {
+                let stream = TcpStream::connect(addr).map_err(|e| format!("Can't connect to {}: {}", addr, e))?;
+                stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+                Ok(SpectatorInput::Socket(stream, Vec::new()))
+            },
+            None => {
+                let file = File::open(source).map_err(|e| format!("Can't open {}: {}", source, e))?;
+                Ok(SpectatorInput::File(BufReader::new(file), String::new()))
+            },
+        }
+    }
+
+    // the next frame to show, if there is one yet
+    pub fn next_frame(&mut self) -> Option<String> {
+        match self {
+            SpectatorInput::File(reader, partial) => {
+                // a file still being written may end part way through a line
+                match reader.read_line(partial) {
+                    Ok(_) if partial.ends_with('\n') => {
+                        let line = partial.trim_end().to_string();
+                        partial.clear();
+                        Some(line)
+                    },
+                    _ => {None},
+                }
+            },
+            SpectatorInput::Socket(stream, received) => {
+                let mut buf = [0; 16 * 1024];
+                while let Ok(n) = stream.read(&mut buf) {
+                    if n == 0 {
+                        break;
+                    }
+                    received.extend_from_slice(&buf[..n]);
+                }
+                let end = received.iter().rposition(|&b| b == b'\n')?;
+                let start = received[..end].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+                let line = String::from_utf8_lossy(&received[start..end]).into_owned();
+                received.drain(..end + 1);
+                Some(line)
+            },
+        }
+    }
+}
@@ -1,40 +1,105 @@
+use std::mem;
+use std::hash::{Hash, Hasher};
 use sdl2::video::{Window, WindowContext};
 use sdl2::render::{Canvas, TextureCreator};
-use rand;
-use rand::Rng;
 use std::f64::consts::PI;
 
 use crate::common;
+use crate::attacks;
 use crate::mother::Mother;
 use crate::base_bricks::BaseBricks;
 use crate::letter_bricks::LetterBricks;
-use crate::bombs::Bombs;
+use crate::bombs::{Bombs, Volley};
 use crate::image::Image;
+use crate::spectator;
 use crate::soundfx::SoundEffect;
 use crate::animation::{AnimationSeq, Animation, Animations};
+use crate::collision::{CollisionMode, CollisionMask, Hitbox};
+use crate::steering::{self, Bounds, Steering};
+use crate::paths::FORMATIONS;
+use crate::difficulty::{Difficulty, Evasion};
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+#[cfg(feature = "debug-overlay")]
+use crate::text::Text;
+#[cfg(feature = "debug-overlay")]
+use crate::debug_overlay;
 
 // for now, all the spider co-ords/speeds to be kept as float and can be reviewed later
 const NUMBER_OF_SPIDERS: usize = 45;
 const INIT_IN_FLIGHT: u32 = 7;
 const MAX_IN_FLIGHT: u32 = 18;
+// for each attack past the last in survival, see attacks::escalation
+const EXTRA_SPIDERS: usize = 6;
+const MAX_SPIDERS: usize = 3 * NUMBER_OF_SPIDERS;
+const EXTRA_IN_FLIGHT: u32 = 2;
+const EXTRA_PACE: f64 = 0.08;
+const MAX_PACE: f64 = 1.8;
+// the daily challenge mutators, see daily.rs
+const FAST_PACE: f64 = 1.3;
+const DOUBLE_BOMB_RATE: f64 = 2.0;
 const SPIDER_WIDTH: f64 = 30.0;
 const SPIDER_HEIGHT: f64 = 40.0;
 const SPIDER_PERIOD: u32 = 20;
 const SPIDER_ROTATE_SPEED: f64 = 0.05;
 const SWOOP_SPEED: f64 = 5.0;
 const FRAMES_BETWEEN_LAUNCHES: u32 = 30;
+const FRAMES_BETWEEN_BURST_LAUNCHES: u32 = 8;
 const FIRST_LAUNCH: u32 = 100;
 const FLIGHT_SPIDER_Y_MAX: f64 = 480.0;
 const FLIGHT_SPIDER_Y_MIN: f64 = 200.0;
 const SPEED_SLOW: f64 = 2.0;
 const SPEED_MEDIUM: f64 = 3.0;
 const SPEED_FAST: f64 = 4.0;
-const SPEED: [[(f64, f64); 3]; 3] =
-    [[(SPEED_SLOW, SPEED_SLOW), (SPEED_SLOW / 2.0, SPEED_SLOW), (0.0, SPEED_SLOW)],
-     [(SPEED_MEDIUM, SPEED_MEDIUM / 2.0), (SPEED_MEDIUM, SPEED_MEDIUM), (SPEED_MEDIUM / 2.0, SPEED_MEDIUM)],
-     [(SPEED_FAST, 0.0), (SPEED_FAST, SPEED_FAST / 2.0), (SPEED_FAST, SPEED_FAST)]];
+// for each type, the heavier ones turn more slowly
+const STEERING: [Steering; SPIDER_TYPES] = [
+    Steering {max_speed: SPEED_SLOW, turn_rate: 0.06, acceleration: 0.1},
+    Steering {max_speed: SPEED_MEDIUM, turn_rate: 0.08, acceleration: 0.15},
+    Steering {max_speed: SPEED_FAST, turn_rate: 0.1, acceleration: 0.2},
+    Steering {max_speed: SPEED_FAST, turn_rate: 0.1, acceleration: 0.2},
+    Steering {max_speed: SPEED_SLOW, turn_rate: 0.04, acceleration: 0.05},
+    Steering {max_speed: SPEED_MEDIUM, turn_rate: 0.12, acceleration: 0.2},
+];
+// a target closer than this is reached
+const ARRIVED_DISTANCE: f64 = 2.0;
+// a missile is seen coming when it is this close below a spider and lined
+// up with it, give or take the margin
+const THREAT_RANGE: f64 = 160.0;
+const THREAT_MARGIN: f64 = 8.0;
+const SIDESTEP_FRAMES: u32 = 8;
+// having decided not to dodge a missile, a spider doesn't think again until
+// it has gone by
+const IGNORE_FRAMES: u32 = 15;
+// how much more likely a laden spider of each type is to dodge, heavy and
+// armoured ones hold their course
+const CARRY_CAUTION: [f64; SPIDER_TYPES] = [0.5, 1.0, 1.5, 1.0, 0.25, 1.5];
 const SPIDER_ASCEND_Y: f64 = 510.0;
 const BOMB_RELEASE_MAX_Y: f64 = 410.0;
+// for each type, the bombs it picks from and how many frames it waits
+// between them, thieves are too busy to bomb anyone
+const BOMBING: [Bombing; SPIDER_TYPES] = [
+    Bombing {volleys: &[Volley::Straight, Volley::Straight, Volley::Split], frames: (60, 220)},
+    Bombing {volleys: &[Volley::Straight, Volley::Straight, Volley::Aimed], frames: (50, 200)},
+    Bombing {volleys: &[Volley::Aimed, Volley::Leading], frames: (40, 170)},
+    Bombing {volleys: &[Volley::Spread], frames: (30, 80)},
+    Bombing {volleys: &[Volley::Straight, Volley::Homing], frames: (80, 240)},
+    Bombing {volleys: &[], frames: (50, 200)},
+];
+// the first attack keeps to the bombs there always were
+const PLAIN_BOMBING: Bombing = Bombing {volleys: &[Volley::Straight], frames: (50, 200)};
+const SPIDER_TYPES: usize = 6;
+const ARMOURED_HIT_POINTS: u32 = 3;
+const FLASH_FRAMES: u32 = 12;
+// divers hover at this height while lining up on a ship
+const DIVER_HOVER_Y: f64 = 250.0;
+const DIVER_TRACK_SPEED: f64 = 3.0;
+const DIVER_LOCK_FRAMES: u32 = 90;
+// the last part of the lock, when the diver stops following the ship and
+// shakes to warn that it is about to dive
+const DIVER_TELEGRAPH_FRAMES: u32 = 30;
+const DIVER_DIVE_SPEED: f64 = 7.0;
+const DIVER_PULL_UP_Y: f64 = 420.0;
+const DIVER_GROUND_Y: f64 = 590.0;
 
 #[derive(Copy, Clone)]
 enum State {
@@ -46,6 +111,18 @@ enum State {
     Ascend,
     Carry(f64, f64, Option<common::TargetBrick>),
     Release(f64, f64),
+    // flying a scripted path, the frame, formation and slot in it
+    Formation(u32, usize, usize),
+    // divers only, lining up on a ship, diving (and whether they will pull
+    // up before the ground) and climbing back up after pulling up
+    Lock(u32),
+    Dive(bool),
+    Climb,
+    // thieves only, heading for a brick in the word, taking it and making
+    // off with it off the top of the screen
+    Raid(f64, f64, Option<common::TargetBrick>),
+    Steal(f64, f64, usize),
+    Escape(usize),
     Dead,
 }
 
@@ -54,12 +131,45 @@ enum Type {
     Slow,
     Medium,
     Fast,
+    Diver,
+    Armoured,
+    Thief,
+}
+
+struct Bombing {
+    volleys: &'static [Volley],
+    frames: (u32, u32),
+}
+
+impl Type {
+    fn steering(&self) -> &'static Steering {
+        &STEERING[*self as usize]
+    }
+
+    fn bombing(&self) -> &'static Bombing {
+        &BOMBING[*self as usize]
+    }
+
+    fn hit_points(&self) -> u32 {
+        match *self {
+            Type::Armoured => {ARMOURED_HIT_POINTS},
+            _ => {1},
+        }
+    }
+}
+
+// seeing a missile coming, waiting to react, then stepping aside
+#[derive(Copy, Clone)]
+enum Dodge {
+    Unaware,
+    Reacting(u32, f64),
+    Sidestep(u32, f64),
+    Ignoring(u32),
 }
 
 enum DirRequired {
     Up,
     Down,
-    Any,
 }
 
 #[derive(Copy, Clone)]
@@ -68,15 +178,61 @@ struct Spider {
     state: State,
     x: f64,
     y: f64,
-    next_dir_change: u32,
+    prev_x: f64,
+    prev_y: f64,
     next_bomb_release: u32,
     anim_offset: u32,
+    hit_points: u32,
+    flash_frames: u32,
+    // the push away from other spiders, worked out before each update
+    crowding: (f64, f64),
+    // where the formation it is flying in started and whether it is
+    // flying to the right (1) or left (-1)
+    path_origin: (f64, f64),
+    path_dir: f64,
+    dodge: Dodge,
+    // how much faster than usual it flies, and how much more often it bombs
+    pace: f64,
+    bomb_rate: f64,
+    plain_bombs: bool,
 }
 
 impl Spider {
     fn new() -> Spider {
         Spider {spider_type: Type::Medium, state: State::Nestle, x: 0.0, y: 0.0,
-            next_dir_change: 0, next_bomb_release: 0, anim_offset: 0}
+            prev_x: 0.0, prev_y: 0.0, next_bomb_release: 0, anim_offset: 0,
+            hit_points: 1, flash_frames: 0, crowding: (0.0, 0.0),
+            path_origin: (0.0, 0.0), path_dir: 1.0, dodge: Dodge::Unaware, pace: 1.0, bomb_rate: 1.0,
+            plain_bombs: false}
+    }
+
+    // the medium spiders are spread through the middle of the mother and
+    // divers take the place of some of them, armoured spiders take the place
+    // of every other fast spider and thieves every third slow one
+    fn type_for(i: usize, divers: usize, armoured: usize, thieves: usize) -> Type {
+        let step = (25 / divers.max(1)).max(1);
+        if i < 11 && i % 3 == 2 && i / 3 < thieves {
+            Type::Thief
+        }
+        else if i < 11 {
+            Type::Slow
+        }
+        else if i > 35 && (i - 36).is_multiple_of(2) && (i - 36) / 2 < armoured {
+            Type::Armoured
+        }
+        else if i > 35 {
+            Type::Fast
+        }
+        else if (i - 11) % step == step - 1 && (i - 11) / step < divers {
+            Type::Diver
+        }
+        else {
+            Type::Medium
+        }
+    }
+
+    fn damaged(&self) -> bool {
+        self.hit_points < self.spider_type.hit_points()
     }
 
     fn alive(&self) -> bool {
@@ -86,6 +242,17 @@ impl Spider {
         }
     }
 
+    // back to its place in the nest, relative to the mother
+    fn nestle(&mut self, nest: usize) {
+        self.hit_points = self.spider_type.hit_points();
+        self.flash_frames = 0;
+        self.y = ((nest / 15) * 8) as f64 - 16.0;
+        self.x = ((nest % 15) * 6 + 5) as f64;
+        self.state = State::Nestle;
+        self.next_bomb_release = 0;
+        self.dodge = Dodge::Unaware;
+    }
+
     fn launch(&mut self, mother: &Mother) -> bool {
         if let Some(d) = mother.launch_dir() {
             // transform coords from relative to mother to centre of spider
@@ -101,81 +268,151 @@ impl Spider {
         }
     }
 
-    fn trajectory_reaches_target(&self, x_target: f64, y_target: f64, x_vel: f64, y_vel: f64) -> bool {
-        (y_target - self.y).signum() == y_vel.signum() &&
-        (y_vel < 0.0 || x_vel.abs() <= y_vel.abs()) &&    // not too shallow on downward approach
-        (((x_target - self.x) / x_vel) * y_vel + self.y - y_target).abs() < y_vel.abs()
+    // all of a formation start from the same place, flying towards the
+    // middle of the screen
+    fn launch_in_formation(&mut self, formation: usize, slot: usize, origin: (f64, f64)) {
+        self.path_origin = origin;
+        self.path_dir = if origin.0 < common::SCREEN_WIDTH as f64 / 2.0 {1.0} else {-1.0};
+        self.state = State::Formation(0, formation, slot);
+        self.follow_path(0, formation, slot);
+        self.prev_x = self.x;
+        self.prev_y = self.y;
     }
 
-    fn new_trajectory_for_target(&self, x_target: f64, y_target: f64) -> Option<(f64, f64)> {
-        let s = self.spider_type as usize;
-        for i in 0..3 {
-            let (x_vel, y_vel) = SPEED[s][i];
-            if self.trajectory_reaches_target(x_target, y_target, x_vel, y_vel) {
-                return Some((x_vel, y_vel));
-            }
-            if self.trajectory_reaches_target(x_target, y_target, -x_vel, y_vel) {
-                return Some((-x_vel, y_vel));
-            }
-            if self.trajectory_reaches_target(x_target, y_target, -x_vel, -y_vel) {
-                return Some((-x_vel, -y_vel));
-            }
-            if self.trajectory_reaches_target(x_target, y_target, x_vel, -y_vel) {
-                return Some((x_vel, -y_vel));
-            }
-        }
-        return None;
+    fn follow_path(&mut self, frame: u32, formation: usize, slot: usize) {
+        let (x, y) = FORMATIONS[formation].position(frame, slot);
+        // the paths start at the nest so only the bottom of the flight band
+        // holds them in, a wide slot near the edge stays on the screen
+        self.x = (self.path_origin.0 + x * self.path_dir - SPIDER_WIDTH * 0.5)
+            .clamp(0.0, common::SCREEN_WIDTH as f64 - SPIDER_WIDTH);
+        self.y = (self.path_origin.1 + y - SPIDER_HEIGHT * 0.5).clamp(0.0, FLIGHT_SPIDER_Y_MAX - SPIDER_HEIGHT);
+    }
+
+    // once out of the nest each type goes its own way
+    fn break_off(&mut self, x_vel: f64, y_vel: f64) {
+        self.state = match self.spider_type {
+            Type::Diver => {State::Lock(0)},
+            Type::Thief => {State::Raid(x_vel, y_vel, None)},
+            _ => {State::Seek(x_vel, y_vel, None)},
+        };
     }
 
-    fn random_sign(n: f64) -> f64 {
-        if rand::thread_rng().gen() {-n} else {n}
+    fn steering(&self) -> Steering {
+        let steering = self.spider_type.steering();
+        Steering {max_speed: steering.max_speed * self.pace, turn_rate: steering.turn_rate * self.pace,
+                  acceleration: steering.acceleration * self.pace}
     }
 
+    // somewhere within 60 degrees of straight down or up at full speed
     fn random_vel(&self, dr: DirRequired) -> (f64, f64) {
-        let (x_vel, y_vel) = SPEED[self.spider_type as usize][rand::thread_rng().gen_range(0..3)];
+        let speed = self.steering().max_speed;
+        let angle = common::random_range(-PI / 3.0..PI / 3.0);
+        let (x_vel, y_vel) = (angle.sin() * speed, angle.cos() * speed);
         match dr {
-            DirRequired::Down => {
-                let y_vel = if y_vel == 0.0 {SPEED_FAST} else {y_vel};
-                (Spider::random_sign(x_vel), y_vel)
-            },
-            DirRequired::Up => {
-                let y_vel = if y_vel == 0.0 {SPEED_FAST} else {y_vel};
-                (Spider::random_sign(x_vel), -y_vel)
-            },
-            DirRequired::Any => {
-                (Spider::random_sign(x_vel), Spider::random_sign(y_vel))
-            },
+            DirRequired::Down => {(x_vel, y_vel)},
+            DirRequired::Up => {(x_vel, -y_vel)},
         }
     }
 
-    fn aimless_wandering(&mut self, x_vel: f64, y_vel: f64) -> (f64, f64) {
-        if self.next_dir_change == 0 {
-            self.next_dir_change = rand::thread_rng().gen_range(100..200);
-        }
-
-        self.next_dir_change -= 1;
-
-        let (x_vel, y_vel) = if self.next_dir_change == 0
-            {self.random_vel(DirRequired::Any)} else {(x_vel, y_vel)};
+    // steer towards the target if there is one, otherwise wander within the
+    // flight band, which stretches to take in the target. Returns the new
+    // velocity, or None once the target is reached
+    fn fly(&mut self, x_vel: f64, y_vel: f64, target: Option<(f64, f64)>) -> Option<(f64, f64)> {
+        let steering = self.steering();
+        let mut bounds = Bounds {x_min: 0.0, x_max: common::SCREEN_WIDTH as f64 - SPIDER_WIDTH,
+            y_min: FLIGHT_SPIDER_Y_MIN, y_max: FLIGHT_SPIDER_Y_MAX - SPIDER_HEIGHT};
+        let desired = match target {
+            Some((target_x, target_y)) => {
+                if (target_x - self.x).hypot(target_y - self.y) < ARRIVED_DISTANCE.max(x_vel.hypot(y_vel)) {
+                    self.x = target_x;
+                    self.y = target_y;
+                    return None;
+                }
+                bounds.y_min = bounds.y_min.min(target_y);
+                bounds.y_max = bounds.y_max.max(target_y);
+                steering::arrive(self.x, self.y, target_x, target_y, steering.max_speed)
+            },
+            None => {steering::wander((x_vel, y_vel), steering.max_speed)},
+        };
+        let desired = (desired.0 + self.crowding.0, desired.1 + self.crowding.1);
+        let desired = steering::contain(self.x, self.y, desired, &bounds, steering.max_speed);
+        let (x_vel, y_vel) = steering.steer((x_vel, y_vel), desired);
+        // bounce off the top and bottom of the band, a spider outside it
+        // (coming up from a brick) is still free to fly back in
+        let y_vel = if (self.y + y_vel > bounds.y_max && y_vel > 0.0) ||
+            (self.y + y_vel < bounds.y_min && y_vel < 0.0)
+            {- y_vel} else {y_vel};
+        self.x = (self.x + x_vel).clamp(bounds.x_min, bounds.x_max);
+        self.y += y_vel;
+        Some((x_vel, y_vel))
+    }
 
-        let x_vel = if (self.x + x_vel > common::SCREEN_WIDTH as f64 - SPIDER_WIDTH && x_vel > 0.0) ||
-            (self.x + x_vel < 0.0 && x_vel < 0.0)
-            {- x_vel} else {x_vel};
+    // the x of a missile about to hit the spider, if there is one
+    fn threat(&self, missiles: &[(i32, i32)]) -> Option<f64> {
+        let centre_x = self.x + SPIDER_WIDTH / 2.0;
+        missiles.iter()
+            .map(|&(x, y)| (x as f64, y as f64))
+            .find(|&(x, y)| (x - centre_x).abs() < SPIDER_WIDTH / 2.0 + THREAT_MARGIN &&
+                y >= self.y && y < self.y + SPIDER_HEIGHT + THREAT_RANGE)
+            .map(|(x, _)| x)
+    }
 
-        let y_vel = if (self.y + y_vel > FLIGHT_SPIDER_Y_MAX - SPIDER_HEIGHT && y_vel > 0.0) ||
-            (self.y + y_vel < FLIGHT_SPIDER_Y_MIN && y_vel < 0.0)
-            {- y_vel} else {y_vel};
-        (x_vel, y_vel)
+    // a spider flying free may see a missile coming and, after a moment to
+    // react, step out of the way of it
+    fn evade(&mut self, missiles: &[(i32, i32)], evasion: &Evasion) {
+        let free = matches!(self.state, State::Seek(..) | State::Carry(..) | State::Raid(..));
+        self.dodge = match self.dodge {
+            Dodge::Reacting(0, dir) => {Dodge::Sidestep(SIDESTEP_FRAMES, dir)},
+            Dodge::Reacting(n, dir) => {Dodge::Reacting(n - 1, dir)},
+            Dodge::Sidestep(n, dir) => {
+                if free {
+                    self.x = (self.x + dir * evasion.sidestep_speed)
+                        .clamp(0.0, common::SCREEN_WIDTH as f64 - SPIDER_WIDTH);
+                }
+                if n > 1 {Dodge::Sidestep(n - 1, dir)} else {Dodge::Unaware}
+            },
+            Dodge::Ignoring(n) => {
+                if n > 1 {Dodge::Ignoring(n - 1)} else {Dodge::Unaware}
+            },
+            Dodge::Unaware => {
+                match self.threat(missiles) {
+                    Some(missile_x) if free && evasion.chance > 0.0 => {
+                        let caution = if let State::Carry(_, _, _) = self.state
+                            {CARRY_CAUTION[self.spider_type as usize]} else {1.0};
+                        if common::random_range(0.0..1.0) < evasion.chance * caution {
+                            // away from the missile unless that is into the edge
+                            let room = SIDESTEP_FRAMES as f64 * evasion.sidestep_speed;
+                            let dir = if self.x + SPIDER_WIDTH / 2.0 < missile_x {-1.0} else {1.0};
+                            let dir = if (dir < 0.0 && self.x < room) ||
+                                (dir > 0.0 && self.x > common::SCREEN_WIDTH as f64 - SPIDER_WIDTH - room)
+                                {- dir} else {dir};
+                            Dodge::Reacting(evasion.reaction_frames, dir)
+                        }
+                        else {
+                            Dodge::Ignoring(IGNORE_FRAMES)
+                        }
+                    },
+                    _ => {Dodge::Unaware},
+                }
+            },
+        };
     }
 
     fn drop_bomb(&mut self, bombs: &mut Bombs) {
-        if self.y < BOMB_RELEASE_MAX_Y {
+        let bombing = match self.spider_type {
+            Type::Thief => {self.spider_type.bombing()},
+            _ if self.plain_bombs => {&PLAIN_BOMBING},
+            _ => {self.spider_type.bombing()},
+        };
+        if self.y < BOMB_RELEASE_MAX_Y && ! bombing.volleys.is_empty() {
             if self.next_bomb_release == 0 {
-                self.next_bomb_release = rand::thread_rng().gen_range(50..200);
+                let frames = common::random_range(bombing.frames.0..bombing.frames.1);
+                self.next_bomb_release = ((frames as f64 / self.bomb_rate) as u32).max(1);
             }
             self.next_bomb_release -= 1;
             if self.next_bomb_release == 0 {
-                bombs.release((self.x + SPIDER_WIDTH / 2.0) as i32, (self.y + SPIDER_HEIGHT) as i32);
+                let volley = bombing.volleys[common::random_range(0..bombing.volleys.len())];
+                bombs.release_volley((self.x + SPIDER_WIDTH / 2.0) as i32, (self.y + SPIDER_HEIGHT) as i32, volley);
             }
         }
     }
@@ -183,6 +420,8 @@ impl Spider {
     fn update(&mut self, base_bricks: &mut BaseBricks, letter_bricks: &mut LetterBricks,
         bombs: &mut Bombs, restrict: bool, take_brick_sound: &SoundEffect,
         deposit_brick_sound: &SoundEffect, sound_on: bool) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
         match self.state {
             State::Swoop(n, r) => {
                 if n < 1.0 {
@@ -194,59 +433,41 @@ impl Spider {
                     // transform coords from centre to top left of spider
                     self.x = (self.x - SPIDER_WIDTH * 0.5).floor();
                     self.y = (self.y - SPIDER_HEIGHT * 0.5).floor();
+                    self.prev_x = self.x;
+                    self.prev_y = self.y;
                     let (x_vel, y_vel) = self.random_vel(DirRequired::Down);
-                    self.state = State::Seek(x_vel, y_vel, None);
+                    self.break_off(x_vel, y_vel);
+                }
+            },
+            State::Formation(n, formation, slot) => {
+                let path = FORMATIONS[formation].path();
+                if n < path.frames() {
+                    self.follow_path(n, formation, slot);
+                    self.state = State::Formation(n + 1, formation, slot);
+                }
+                else {
+                    // leave the path heading the way it was going
+                    let heading = path.heading(n);
+                    let speed = self.steering().max_speed;
+                    self.break_off(heading.cos() * speed * self.path_dir, heading.sin() * speed);
                 }
             },
             State::Seek(x_vel, y_vel, target) => {
-                match target {
-                    Some(target_brick) => {
-                        // caculate adjusted target x,y for top left of spider + a little bit of
-                        // descend space
-                        let adj_x = target_brick.x as f64 - 9.0;
-                        let adj_y = target_brick.y as f64 - 40.0;
-
-                        // restricted mode?
-                        if restrict {
-                            let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                            self.state = State::Seek(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
-                        }
-                        // have we reached the target (or close enough)?
-                        else if (self.x - adj_x).abs() < x_vel.abs() &&
-                           (self.y - adj_y).abs() < y_vel.abs() {
-                            self.x = adj_x;
-                            self.y = adj_y;
-                            self.state = State::Descend(target_brick);
-                        }
-                        // Are we already on a trajectory to reach the target?
-                        else if self.trajectory_reaches_target(adj_x, adj_y, x_vel, y_vel) {
-                            // stay as we are!
-                            self.x += x_vel;
-                            self.y += y_vel;
-                        }
-                        // can we take a trajectory now that will reach the target?
-                        else if let Some((new_x_vel, new_y_vel)) = self.new_trajectory_for_target(adj_x, adj_y) {
-                            // change direction
-                            self.state = State::Seek(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
-                        }
-                        else {
-                            let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                            self.state = State::Seek(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
-                        }
+                // caculate adjusted target x,y for top left of spider + a little bit of
+                // descend space, the target is ignored in restricted mode
+                let adj = target.filter(|_| ! restrict)
+                    .map(|target_brick| (target_brick.x as f64 - 9.0, target_brick.y as f64 - 40.0));
+                match (self.fly(x_vel, y_vel, adj), target) {
+                    (None, Some(target_brick)) => {
+                        self.state = State::Descend(target_brick);
+                    },
+                    (Some((x_vel, y_vel)), None) => {
+                        self.state = State::Seek(x_vel, y_vel, base_bricks.request_target(self.x, self.y));
                     },
-                    None => {
-                        let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                        let new_target = base_bricks.request_target();
-                        self.state = State::Seek(new_x_vel, new_y_vel, new_target);
-                        self.x += new_x_vel;
-                        self.y += new_y_vel;
+                    (Some((x_vel, y_vel)), target) => {
+                        self.state = State::Seek(x_vel, y_vel, target);
                     },
+                    (None, None) => {},
                 }
                 if ! restrict {
                     self.drop_bomb(bombs);
@@ -255,10 +476,15 @@ impl Spider {
             State::Descend(target) => {
                 self.y += 1.0;
                 if self.y >= target.y as f64 - SPIDER_HEIGHT + 8.0 {
-                    base_bricks.take_target(target.brick_id);
-                    self.state = State::Grab(0.0, if rand::thread_rng().gen() {-1.0} else {1.0});
-                    if sound_on {
-                        take_brick_sound.play();
+                    if base_bricks.take_target(target.brick_id) {
+                        self.state = State::Grab(0.0, if common::random_bool() {-1.0} else {1.0});
+                        if sound_on {
+                            take_brick_sound.play();
+                        }
+                    }
+                    else {
+                        // the brick was bombed, so look for another
+                        self.state = State::Seek(0.0, -1.0, None);
                     }
                 }
             },
@@ -271,67 +497,31 @@ impl Spider {
                 }
             },
             State::Ascend => {
-                self.y -= match self.spider_type {
-                    Type::Slow => {SPEED_SLOW},
-                    Type::Medium => {SPEED_MEDIUM},
-                    Type::Fast => {SPEED_FAST}};
+                self.y -= self.steering().max_speed;
                 if self.y <= SPIDER_ASCEND_Y {
                     let (x_vel, y_vel) = self.random_vel(DirRequired::Up);
                     self.state = State::Carry(x_vel, y_vel, None);
                 }
             },
             State::Carry(x_vel, y_vel, target) => {
-                match target {
-                    Some(target_brick) => {
-                        // caculate adjusted target x,y for top left of spider
-                        let adj_x = (target_brick.x - 9) as f64;
-                        let adj_y = target_brick.y as f64;
-
-                        // restricted mode?
-                        if restrict {
-                            let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                            self.state = State::Carry(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
-                        }
-                        // have we reached the target (or close enough)?
-                        else if (self.x - adj_x).abs() < x_vel.abs() &&
-                           (self.y - adj_y).abs() < y_vel.abs() {
-                            letter_bricks.fill_target(target_brick.brick_id);
-                            self.state = State::Release(0.0, if rand::thread_rng().gen() {-1.0} else {1.0});
-                            self.x = adj_x;
-                            self.y = adj_y;
-                            if sound_on {
-                                deposit_brick_sound.play();
-                            }
-                        }
-                        // Are we already on a trajectory to reach the target?
-                        else if self.trajectory_reaches_target(adj_x, adj_y, x_vel, y_vel) {
-                            // stay as we are!
-                            self.x += x_vel;
-                            self.y += y_vel;
-                        }
-                        // can we take a trajectory now that will reach the target?
-                        else if let Some((new_x_vel, new_y_vel)) = self.new_trajectory_for_target(adj_x, adj_y) {
-                            // change direction
-                            self.state = State::Carry(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
-                        }
-                        else {
-                            let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                            self.state = State::Carry(new_x_vel, new_y_vel, target);
-                            self.x += new_x_vel;
-                            self.y += new_y_vel;
+                // caculate adjusted target x,y for top left of spider
+                let adj = target.filter(|_| ! restrict)
+                    .map(|target_brick| ((target_brick.x - 9) as f64, target_brick.y as f64));
+                match (self.fly(x_vel, y_vel, adj), target) {
+                    (None, Some(target_brick)) => {
+                        letter_bricks.fill_target(target_brick.brick_id);
+                        self.state = State::Release(0.0, if common::random_bool() {-1.0} else {1.0});
+                        if sound_on {
+                            deposit_brick_sound.play();
                         }
                     },
-                    None => {
-                        let (new_x_vel, new_y_vel) = self.aimless_wandering(x_vel, y_vel);
-                        let new_target = letter_bricks.request_target();
-                        self.state = State::Carry(new_x_vel, new_y_vel, new_target);
-                        self.x += new_x_vel;
-                        self.y += new_y_vel;
+                    (Some((x_vel, y_vel)), None) => {
+                        self.state = State::Carry(x_vel, y_vel, letter_bricks.request_target(self.x, self.y));
                     },
+                    (Some((x_vel, y_vel)), target) => {
+                        self.state = State::Carry(x_vel, y_vel, target);
+                    },
+                    (None, None) => {},
                 }
                 if ! restrict {
                     self.drop_bomb(bombs);
@@ -350,38 +540,189 @@ impl Spider {
         }
     }
 
-    fn collision(&self, col_area: &common::ScreenObjectArea) -> bool {
-        let collides =  col_area.collides(
-            common::ScreenObjectArea::new(self.x as i32, self.y as i32, SPIDER_WIDTH as u32, SPIDER_HEIGHT as u32));
+    // divers ignore the bricks, they follow the nearest ship then dive at it
+    fn update_diver(&mut self, bombs: &mut Bombs, ship_xs: &[i32], restrict: bool) {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
+        match self.state {
+            State::Lock(n) => {
+                self.y += (DIVER_HOVER_Y - self.y).clamp(-2.0, 2.0);
+                let centre_x = self.x + SPIDER_WIDTH / 2.0;
+                let target_x = ship_xs.iter()
+                    .map(|&x| x as f64)
+                    .min_by(|a, b| (a - centre_x).abs().total_cmp(&(b - centre_x).abs()));
+                match target_x {
+                    Some(target_x) if ! restrict => {
+                        if n < DIVER_LOCK_FRAMES - DIVER_TELEGRAPH_FRAMES {
+                            self.x += (target_x - centre_x).clamp(-DIVER_TRACK_SPEED, DIVER_TRACK_SPEED);
+                            self.drop_bomb(bombs);
+                        }
+                        if n < DIVER_LOCK_FRAMES {
+                            self.state = State::Lock(n + 1);
+                        }
+                        else {
+                            self.state = State::Dive(common::random_bool());
+                        }
+                    },
+                    // nothing to dive at, so start again
+                    _ => {self.state = State::Lock(0);},
+                }
+            },
+            State::Dive(pull_up) => {
+                self.y += DIVER_DIVE_SPEED;
+                if pull_up && self.y >= DIVER_PULL_UP_Y {
+                    self.state = State::Climb;
+                }
+            },
+            State::Climb => {
+                self.y -= SPEED_FAST;
+                if self.y <= DIVER_HOVER_Y {
+                    self.state = State::Lock(0);
+                }
+            },
+            _ => {},
+        }
+    }
 
+    // thieves go for a brick in a finished row of the word and make off with
+    // it, returns true once one has got away
+    fn update_thief(&mut self, letter_bricks: &mut LetterBricks, restrict: bool) -> bool {
+        self.prev_x = self.x;
+        self.prev_y = self.y;
         match self.state {
-            State::Seek(_, _, _) => {collides},
-            State::Ascend => {collides},
-            State::Carry(_, _, _) => {collides},
-            State::Release(_, _) => {collides},  // this is not really accurate as rotating 
+            State::Raid(x_vel, y_vel, target) => {
+                let adj = target.filter(|_| ! restrict)
+                    .map(|target_brick| ((target_brick.x - 9) as f64, target_brick.y as f64));
+                match (self.fly(x_vel, y_vel, adj), target) {
+                    (None, Some(target_brick)) => {
+                        letter_bricks.take_brick(target_brick.brick_id);
+                        let r = if common::random_bool() {-1.0} else {1.0};
+                        self.state = State::Steal(0.0, r, target_brick.brick_id);
+                    },
+                    (Some((x_vel, y_vel)), None) => {
+                        self.state = State::Raid(x_vel, y_vel, letter_bricks.request_theft());
+                    },
+                    (Some((x_vel, y_vel)), target) => {
+                        self.state = State::Raid(x_vel, y_vel, target);
+                    },
+                    (None, None) => {},
+                }
+            },
+            State::Steal(n, r, brick_id) => {
+                if n < 1.0 {
+                    self.state = State::Steal(n + SPIDER_ROTATE_SPEED, r, brick_id);
+                }
+                else {
+                    self.state = State::Escape(brick_id);
+                }
+            },
+            State::Escape(_) => {
+                self.y -= SPEED_FAST;
+                return self.y < - SPIDER_HEIGHT;
+            },
+            _ => {},
+        }
+        false
+    }
+
+    // shaking from side to side while telegraphing a dive
+    fn telegraph_angle(&self) -> f64 {
+        match self.state {
+            State::Lock(n) if n >= DIVER_LOCK_FRAMES - DIVER_TELEGRAPH_FRAMES => {
+                if (n / 3) % 2 == 0 {15.0} else {-15.0}
+            },
+            _ => {0.0},
+        }
+    }
+
+    fn area(&self) -> common::ScreenObjectArea {
+        common::ScreenObjectArea::new(self.x as i32, self.y as i32, SPIDER_WIDTH as u32, SPIDER_HEIGHT as u32)
+            .moving(self.x as i32 - self.prev_x as i32, self.y as i32 - self.prev_y as i32)
+    }
+
+    fn collidable(&self) -> bool {
+        match self.state {
+            State::Seek(_, _, _) => {true},
+            State::Ascend => {true},
+            State::Carry(_, _, _) => {true},
+            State::Release(_, _) => {true},  // this is not really accurate as rotating 
+            State::Formation(_, _, _) => {true},
+            State::Lock(_) => {true},
+            State::Dive(_) => {true},
+            State::Climb => {true},
+            State::Raid(_, _, _) => {true},
+            State::Steal(_, _, _) => {true},
+            State::Escape(_) => {true},
             _ => {false}
         }
     }
+
+    #[cfg(feature = "debug-overlay")]
+    fn debug_state(&self) -> (&'static str, (f64, f64), Option<common::TargetBrick>) {
+        match self.state {
+            State::Nestle => {("Nestle", (0.0, 0.0), None)},
+            State::Swoop(_, _) => {("Swoop", (0.0, 0.0), None)},
+            State::Seek(x_vel, y_vel, target) => {("Seek", (x_vel, y_vel), target)},
+            State::Descend(target) => {("Descend", (0.0, 1.0), Some(target))},
+            State::Grab(_, _) => {("Grab", (0.0, 0.0), None)},
+            State::Ascend => {("Ascend", (0.0, -1.0), None)},
+            State::Carry(x_vel, y_vel, target) => {("Carry", (x_vel, y_vel), target)},
+            State::Release(_, _) => {("Release", (0.0, 0.0), None)},
+            State::Formation(_, _, _) => {("Formation", (self.x - self.prev_x, self.y - self.prev_y), None)},
+            State::Lock(_) => {("Lock", (0.0, 0.0), None)},
+            State::Dive(_) => {("Dive", (0.0, DIVER_DIVE_SPEED), None)},
+            State::Climb => {("Climb", (0.0, -SPEED_FAST), None)},
+            State::Raid(x_vel, y_vel, target) => {("Raid", (x_vel, y_vel), target)},
+            State::Steal(_, _, _) => {("Steal", (0.0, 0.0), None)},
+            State::Escape(_) => {("Escape", (0.0, -SPEED_FAST), None)},
+            State::Dead => {("Dead", (0.0, 0.0), None)},
+        }
+    }
 }
 
 pub struct Spiders<'a> {
-    spider_image_empty: [[Image<'a>; 4]; 3],
-    spider_image_laden: [[Image<'a>; 4]; 3],
+    spider_image_empty: [[Image<'a>; 4]; SPIDER_TYPES],
+    spider_image_laden: [[Image<'a>; 4]; SPIDER_TYPES],
+    // armoured spiders look different once damaged and flash when hit,
+    // empty then laden
+    spider_image_damaged: [[Image<'a>; 4]; 2],
+    spider_image_flash: [[Image<'a>; 4]; 2],
+    spider_mask_empty: [CollisionMask; SPIDER_TYPES],
+    spider_mask_laden: [CollisionMask; SPIDER_TYPES],
+    collision_mode: CollisionMode,
     spiders_left: u32,
     spiders_in_flight: u32,
     next_spider_launch: usize,
     last_launch_frame: u32,
     max_spiders_in_flight: u32,
     next_wave_countdown: u32,
-    spider: [Spider; NUMBER_OF_SPIDERS],
+    // the formations the attack starts with, in order, and the next to go
+    formations: &'static [usize],
+    next_formation: usize,
+    spider: Vec<Spider>,
+    // whether attacks past the last one keep getting harder, for survival
+    escalating: bool,
+    // whether spiders that are shot go back to the nest, for time attack
+    respawning: bool,
+    // daily challenge mutators
+    fast: bool,
+    double_bombs: bool,
     take_brick_sound: SoundEffect,
     deposit_brick_sound: SoundEffect,
     spider_explode_sound: SoundEffect,
+    armour_hit_sound: SoundEffect,
     sound_on: bool,
+    // slowed spiders only move every other frame
+    slowed: bool,
+    // where the ships are, for divers to aim at
+    ship_xs: Vec<i32>,
+    // the tips of the missiles in flight and how the spiders dodge them
+    missiles: Vec<(i32, i32)>,
+    evasion: &'static Evasion,
 }
 
 impl<'a> Spiders<'a> {
-    pub fn new(texture_creator: &TextureCreator<WindowContext>) -> Spiders {
+    pub fn new(texture_creator: &TextureCreator<WindowContext>, collision_mode: CollisionMode) -> Spiders<'_> {
         let w = SPIDER_WIDTH as u32;
         let h = SPIDER_HEIGHT as u32;
         let mut new_spiders = Spiders {
@@ -397,7 +738,20 @@ impl<'a> Spiders<'a> {
                  [Image::new(texture_creator, "spider_empty3_1.png", w, h),
                   Image::new(texture_creator, "spider_empty3_2.png", w, h),
                   Image::new(texture_creator, "spider_empty3_3.png", w, h),
-                  Image::new(texture_creator, "spider_empty3_4.png", w, h)]],
+                  Image::new(texture_creator, "spider_empty3_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_diver_1.png", w, h),
+                  Image::new(texture_creator, "spider_diver_2.png", w, h),
+                  Image::new(texture_creator, "spider_diver_3.png", w, h),
+                  Image::new(texture_creator, "spider_diver_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_armoured_empty_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_empty_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_empty_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_empty_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_thief_empty_1.png", w, h),
+                  Image::new(texture_creator, "spider_thief_empty_2.png", w, h),
+                  Image::new(texture_creator, "spider_thief_empty_3.png", w, h),
+                  Image::new(texture_creator, "spider_thief_empty_4.png", w, h)]],
+            // divers never carry a brick
             spider_image_laden:
                 [[Image::new(texture_creator, "spider_laden1_1.png", w, h),
                   Image::new(texture_creator, "spider_laden1_2.png", w, h),
@@ -410,57 +764,144 @@ impl<'a> Spiders<'a> {
                  [Image::new(texture_creator, "spider_laden3_1.png", w, h),
                   Image::new(texture_creator, "spider_laden3_2.png", w, h),
                   Image::new(texture_creator, "spider_laden3_3.png", w, h),
-                  Image::new(texture_creator, "spider_laden3_4.png", w, h)]],
+                  Image::new(texture_creator, "spider_laden3_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_diver_1.png", w, h),
+                  Image::new(texture_creator, "spider_diver_2.png", w, h),
+                  Image::new(texture_creator, "spider_diver_3.png", w, h),
+                  Image::new(texture_creator, "spider_diver_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_armoured_laden_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_laden_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_laden_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_laden_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_thief_laden_1.png", w, h),
+                  Image::new(texture_creator, "spider_thief_laden_2.png", w, h),
+                  Image::new(texture_creator, "spider_thief_laden_3.png", w, h),
+                  Image::new(texture_creator, "spider_thief_laden_4.png", w, h)]],
+            spider_image_damaged:
+                [[Image::new(texture_creator, "spider_armoured_damaged_empty_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_empty_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_empty_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_empty_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_armoured_damaged_laden_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_laden_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_laden_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_damaged_laden_4.png", w, h)]],
+            spider_image_flash:
+                [[Image::new(texture_creator, "spider_armoured_flash_empty_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_empty_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_empty_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_empty_4.png", w, h)],
+                 [Image::new(texture_creator, "spider_armoured_flash_laden_1.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_laden_2.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_laden_3.png", w, h),
+                  Image::new(texture_creator, "spider_armoured_flash_laden_4.png", w, h)]],
+            spider_mask_empty:
+                [CollisionMask::new(&["spider_empty1_1.png", "spider_empty1_2.png",
+                                      "spider_empty1_3.png", "spider_empty1_4.png"]),
+                 CollisionMask::new(&["spider_empty2_1.png", "spider_empty2_2.png",
+                                      "spider_empty2_3.png", "spider_empty2_4.png"]),
+                 CollisionMask::new(&["spider_empty3_1.png", "spider_empty3_2.png",
+                                      "spider_empty3_3.png", "spider_empty3_4.png"]),
+                 CollisionMask::new(&["spider_diver_1.png", "spider_diver_2.png",
+                                      "spider_diver_3.png", "spider_diver_4.png"]),
+                 CollisionMask::new(&["spider_armoured_empty_1.png", "spider_armoured_empty_2.png",
+                                      "spider_armoured_empty_3.png", "spider_armoured_empty_4.png"]),
+                 CollisionMask::new(&["spider_thief_empty_1.png", "spider_thief_empty_2.png",
+                                      "spider_thief_empty_3.png", "spider_thief_empty_4.png"])],
+            spider_mask_laden:
+                [CollisionMask::new(&["spider_laden1_1.png", "spider_laden1_2.png",
+                                      "spider_laden1_3.png", "spider_laden1_4.png"]),
+                 CollisionMask::new(&["spider_laden2_1.png", "spider_laden2_2.png",
+                                      "spider_laden2_3.png", "spider_laden2_4.png"]),
+                 CollisionMask::new(&["spider_laden3_1.png", "spider_laden3_2.png",
+                                      "spider_laden3_3.png", "spider_laden3_4.png"]),
+                 CollisionMask::new(&["spider_diver_1.png", "spider_diver_2.png",
+                                      "spider_diver_3.png", "spider_diver_4.png"]),
+                 CollisionMask::new(&["spider_armoured_laden_1.png", "spider_armoured_laden_2.png",
+                                      "spider_armoured_laden_3.png", "spider_armoured_laden_4.png"]),
+                 CollisionMask::new(&["spider_thief_laden_1.png", "spider_thief_laden_2.png",
+                                      "spider_thief_laden_3.png", "spider_thief_laden_4.png"])],
+            collision_mode,
             spiders_left: NUMBER_OF_SPIDERS as u32,
             spiders_in_flight: 0,
             next_spider_launch: 0,
             last_launch_frame: 0,
             max_spiders_in_flight: 10, // value will only last for first demo
             next_wave_countdown: 0,
-            spider: [Spider::new(); NUMBER_OF_SPIDERS],
+            formations: &[],
+            next_formation: 0,
+            spider: vec![Spider::new(); NUMBER_OF_SPIDERS],
+            escalating: false,
+            respawning: false,
+            fast: false,
+            double_bombs: false,
             take_brick_sound: SoundEffect::new("grab.ogg"),
             deposit_brick_sound: SoundEffect::new("drop.ogg"),
             spider_explode_sound: SoundEffect::new("spider_explosion.ogg"),
-            sound_on: false,};
+            armour_hit_sound: SoundEffect::new("armour_hit.wav"),
+            sound_on: false,
+            slowed: false,
+            ship_xs: Vec::with_capacity(2),
+            missiles: Vec::with_capacity(8),
+            evasion: Difficulty::Normal.evasion(),};
         for i in 0..NUMBER_OF_SPIDERS {
-            if i < 11 {
-                new_spiders.spider[i].spider_type = Type::Slow;
-            }
-            else if i > 35 {
-                new_spiders.spider[i].spider_type = Type::Fast;
-            }
+            new_spiders.spider[i].spider_type = Spider::type_for(i, 0, 0, 0);
             // nestle spiders are 6 by 8
             // for nestle spiders, x and y are relative to mother
             new_spiders.spider[i].y = ((i / 15) * 8) as f64 - 16.0;
             new_spiders.spider[i].x = ((i % 15) * 6 + 5) as f64;
-            new_spiders.spider[i].anim_offset = rand::thread_rng().gen_range(0..SPIDER_PERIOD);
+            new_spiders.spider[i].anim_offset = common::random_range(0..SPIDER_PERIOD);
         }
         new_spiders
     }
 
     pub fn reset(&mut self, screen: u32) {
-        for i in 0..NUMBER_OF_SPIDERS {
-            self.spider[i].y = ((i / 15) * 8) as f64 - 16.0;
-            self.spider[i].x = ((i % 15) * 6 + 5) as f64;
-            self.spider[i].state = State::Nestle;
-            self.spider[i].next_dir_change = 0;
-            self.spider[i].next_bomb_release = 0;
+        let attack = attacks::attack(screen);
+        // the extra spiders are fast ones and share the places in the nest
+        let escalation = if self.escalating {attacks::escalation(screen)} else {0};
+        let number = (NUMBER_OF_SPIDERS + escalation as usize * EXTRA_SPIDERS).min(MAX_SPIDERS);
+        self.spider.truncate(number);
+        while self.spider.len() < number {
+            let mut spider = Spider::new();
+            spider.anim_offset = common::random_range(0..SPIDER_PERIOD);
+            self.spider.push(spider);
+        }
+        let pace = (1.0 + escalation as f64 * EXTRA_PACE).min(MAX_PACE) * if self.fast {FAST_PACE} else {1.0};
+        let bomb_rate = pace * if self.double_bombs {DOUBLE_BOMB_RATE} else {1.0};
+        for i in 0..number {
+            self.spider[i].spider_type = Spider::type_for(i, attack.divers, attack.armoured, attack.thieves);
+            self.spider[i].nestle(i % NUMBER_OF_SPIDERS);
+            self.spider[i].pace = pace;
+            self.spider[i].bomb_rate = bomb_rate;
+            self.spider[i].plain_bombs = attack.plain_bombs;
         }
-        self.spiders_left = NUMBER_OF_SPIDERS as u32;
+        self.spiders_left = number as u32;
         self.spiders_in_flight = 0;
         self.next_spider_launch = 0;
         self.last_launch_frame = 0;
         self.max_spiders_in_flight =
-            (INIT_IN_FLIGHT + screen - 1).min(MAX_IN_FLIGHT);
+            (INIT_IN_FLIGHT + screen - 1).min(MAX_IN_FLIGHT + escalation * EXTRA_IN_FLIGHT);
         self.next_wave_countdown = 0;
+        self.formations = attack.formations;
+        self.next_formation = 0;
     }
 
     pub fn update(&mut self, mother: &Mother, base_bricks: &mut BaseBricks,
                   letter_bricks: &mut LetterBricks, bombs: &mut Bombs,
                   restrict: bool, frame_count: u32) {
+        for s in self.spider.iter_mut().filter(|s| s.flash_frames > 0) {
+            s.flash_frames -= 1;
+        }
+        if self.slowed && frame_count % 2 == 1 {
+            for s in self.spider.iter_mut() {
+                s.prev_x = s.x;
+                s.prev_y = s.y;
+            }
+            return;
+        }
         if self.spiders_in_flight == self.max_spiders_in_flight &&
            self.next_wave_countdown <= 0 {
-            self.next_wave_countdown = rand::thread_rng().gen_range(200..400);
+            self.next_wave_countdown = common::random_range(200..400);
         }
         else if self.spiders_in_flight == 0 {
             self.next_wave_countdown = 0;
@@ -468,26 +909,171 @@ impl<'a> Spiders<'a> {
         else if self.next_wave_countdown > 0 {
             self.next_wave_countdown -= 1;
         }
+        if self.respawning && ! mother.defeated() {
+            self.respawn();
+        }
+        // a boss mother throws out a burst without waiting for the next wave
+        let bursting = mother.bursting();
+        let launch_gap = if bursting {FRAMES_BETWEEN_BURST_LAUNCHES} else {FRAMES_BETWEEN_LAUNCHES};
         if self.spiders_in_flight < self.max_spiders_in_flight &&
-           self.next_wave_countdown <= 0 &&
-           self.next_spider_launch < NUMBER_OF_SPIDERS &&
+           (self.next_wave_countdown <= 0 || bursting) &&
+           self.next_spider_launch < self.spider.len() &&
            frame_count > FIRST_LAUNCH &&
-           frame_count - self.last_launch_frame >= FRAMES_BETWEEN_LAUNCHES &&
-           ! restrict &&
-           self.spider[self.next_spider_launch].launch(mother) {
-            self.next_spider_launch += 1;
-            self.spiders_in_flight += 1;
-            self.last_launch_frame = frame_count;
+           frame_count - self.last_launch_frame >= launch_gap &&
+           ! restrict {
+            match self.formations.get(self.next_formation) {
+                Some(&formation) => {
+                    self.launch_formation(mother, formation, frame_count);
+                },
+                None => {
+                    // once respawning stops the next one may be flying or
+                    // dead, so move on to one still in the nest
+                    let spider = &mut self.spider[self.next_spider_launch];
+                    if let State::Nestle = spider.state {
+                        if spider.launch(mother) {
+                            self.next_spider_launch += 1;
+                            self.spiders_in_flight += 1;
+                            self.last_launch_frame = frame_count;
+                        }
+                    }
+                    else {
+                        self.next_spider_launch += 1;
+                    }
+                },
+            }
         }
+        let flying: Vec<(f64, f64)> = self.spider.iter()
+            .filter(|s| s.collidable())
+            .map(|s| (s.x, s.y))
+            .collect();
+        for s in self.spider.iter_mut().filter(|s| s.collidable()) {
+            s.crowding = steering::separation(s.x, s.y, &flying);
+        }
+        let mut escaped = 0;
         for s in self.spider.iter_mut().filter(|s| match s.state {State::Dead => {false}, _ => {true}}) {
-            s.update(base_bricks, letter_bricks, bombs, restrict,
-                &self.take_brick_sound, &self.deposit_brick_sound, self.sound_on);
+            match s.state {
+                State::Lock(_) | State::Dive(_) | State::Climb => {
+                    s.update_diver(bombs, &self.ship_xs, restrict);
+                },
+                State::Raid(_, _, _) | State::Steal(_, _, _) | State::Escape(_) => {
+                    if s.update_thief(letter_bricks, restrict) {
+                        s.state = State::Dead;
+                        escaped += 1;
+                    }
+                },
+                _ => {
+                    s.update(base_bricks, letter_bricks, bombs, restrict,
+                        &self.take_brick_sound, &self.deposit_brick_sound, self.sound_on);
+                },
+            }
+            if ! restrict {
+                s.evade(&self.missiles, self.evasion);
+            }
         }
+        self.spiders_in_flight -= escaped;
+        self.spiders_left -= escaped;
     }
 
-    pub fn collision(&mut self, col_area: common::ScreenObjectArea) -> Option<usize> {
-        for i in 0..NUMBER_OF_SPIDERS {
-            if self.spider[i].collision(&col_area) {
+    // dead spiders go straight back to their places in the nest, and once
+    // the last in the nest has gone launching starts again from the first
+    // that is back
+    fn respawn(&mut self) {
+        for i in 0..self.spider.len() {
+            if let State::Dead = self.spider[i].state {
+                self.spider[i].nestle(i % NUMBER_OF_SPIDERS);
+                self.spiders_left += 1;
+            }
+        }
+        let nestled = |s: &Spider| matches!(s.state, State::Nestle);
+        if ! self.spider.get(self.next_spider_launch).is_some_and(nestled) {
+            let next = self.spider.iter().skip(self.next_spider_launch).position(nestled)
+                .map(|i| i + self.next_spider_launch)
+                .or_else(|| self.spider.iter().position(nestled));
+            self.next_spider_launch = next.unwrap_or(self.spider.len());
+        }
+    }
+
+    // the next few spiders in the nest leave together once there is room
+    // for all of them
+    fn launch_formation(&mut self, mother: &Mother, formation: usize, frame_count: u32) {
+        let size = FORMATIONS[formation].slots.len();
+        if mother.launch_dir().is_none() ||
+           self.next_spider_launch + size > self.spider.len() ||
+           self.spider[self.next_spider_launch..self.next_spider_launch + size].iter()
+               .any(|s| ! matches!(s.state, State::Nestle)) ||
+           self.spiders_in_flight + size as u32 > self.max_spiders_in_flight {
+            return;
+        }
+        let (mother_x, mother_y) = mother.location();
+        let leader = &self.spider[self.next_spider_launch];
+        let origin = (leader.x + mother_x as f64 + SPIDER_WIDTH * 0.1, leader.y + mother_y as f64);
+        for slot in 0..size {
+            self.spider[self.next_spider_launch + slot].launch_in_formation(formation, slot, origin);
+        }
+        self.next_spider_launch += size;
+        self.spiders_in_flight += size as u32;
+        self.last_launch_frame = frame_count;
+        self.next_formation += 1;
+    }
+
+    // the centres of the ships that divers can go for
+    pub fn set_ship_positions(&mut self, ship_xs: &[i32]) {
+        self.ship_xs.clear();
+        self.ship_xs.extend_from_slice(ship_xs);
+    }
+
+    pub fn set_missile_positions(&mut self, missiles: &[(i32, i32)]) {
+        self.missiles.clear();
+        self.missiles.extend_from_slice(missiles);
+    }
+
+    pub fn set_evasion(&mut self, evasion: &'static Evasion) {
+        self.evasion = evasion;
+    }
+
+    pub fn set_escalating(&mut self, escalating: bool) {
+        self.escalating = escalating;
+    }
+
+    pub fn set_respawning(&mut self, respawning: bool) {
+        self.respawning = respawning;
+    }
+
+    pub fn set_fast(&mut self, fast: bool) {
+        self.fast = fast;
+    }
+
+    pub fn set_double_bombs(&mut self, double_bombs: bool) {
+        self.double_bombs = double_bombs;
+    }
+
+    pub fn set_slowed(&mut self, slowed: bool) {
+        self.slowed = slowed;
+    }
+
+    pub fn set_collision_mode(&mut self, collision_mode: CollisionMode) {
+        self.collision_mode = collision_mode;
+    }
+
+    // the hitbox follows the sprite, including rotation when grabbing
+    // and releasing bricks
+    fn hitbox(&self, spider: &Spider) -> Hitbox<'_> {
+        let type_i = spider.spider_type as usize;
+        let (angle, mask) = match spider.state {
+            State::Grab(n, r) => {(180.0 + 180.0 * n * r, &self.spider_mask_laden[type_i])},
+            State::Ascend => {(0.0, &self.spider_mask_laden[type_i])},
+            State::Carry(_, _, _) => {(0.0, &self.spider_mask_laden[type_i])},
+            State::Steal(n, r, _) => {(180.0 + 180.0 * n * r, &self.spider_mask_laden[type_i])},
+            State::Escape(_) => {(0.0, &self.spider_mask_laden[type_i])},
+            State::Release(n, r) => {(180.0 + 180.0 * n * r, &self.spider_mask_empty[type_i])},
+            _ => {(spider.telegraph_angle(), &self.spider_mask_empty[type_i])},
+        };
+        Hitbox::new(spider.area()).rotated(angle).shaped(self.collision_mode, mask)
+    }
+
+    pub fn collision(&mut self, hitbox: &Hitbox) -> Option<usize> {
+        for i in 0..self.spider.len() {
+            if self.spider[i].collidable() && self.hitbox(&self.spider[i]).collides(hitbox) {
                 return Some(i);
             }
         }
@@ -512,6 +1098,7 @@ impl<'a> Spiders<'a> {
                     None
                 }
             },
+            State::Raid(_, _, target) => {target.map(|target_brick| target_brick.brick_id)},
             _ => {None},
         }
     }
@@ -523,10 +1110,48 @@ impl<'a> Spiders<'a> {
         }
     }
 
+    // whether the target brick is in the word rather than the piles
+    pub fn targets_letter(&self, spider_id: usize) -> bool {
+        matches!(self.spider[spider_id].state, State::Carry(..) | State::Raid(..))
+    }
+
+    // the brick a thief has taken from the word
+    pub fn stolen_brick(&self, spider_id: usize) -> Option<usize> {
+        match self.spider[spider_id].state {
+            State::Steal(_, _, brick_id) => {Some(brick_id)},
+            State::Escape(brick_id) => {Some(brick_id)},
+            _ => {None},
+        }
+    }
+
     pub fn spider_type(&self, spider_id: usize) -> usize {
         self.spider[spider_id].spider_type as usize
     }
 
+    // armoured spiders take more than one hit, true when this was the last
+    pub fn hit(&mut self, spider_id: usize) -> bool {
+        let spider = &mut self.spider[spider_id];
+        if spider.hit_points > 1 {
+            spider.hit_points -= 1;
+            spider.flash_frames = FLASH_FRAMES;
+            if self.sound_on {
+                self.armour_hit_sound.play();
+            }
+            false
+        }
+        else {
+            true
+        }
+    }
+
+    // a wounded carrier lets go of its brick, which goes back to the piles
+    pub fn drop_brick(&mut self, spider_id: usize, base_bricks: &mut BaseBricks) {
+        if let State::Carry(x_vel, y_vel, _) = self.spider[spider_id].state {
+            self.spider[spider_id].state = State::Seek(x_vel, y_vel, None);
+            base_bricks.return_brick();
+        }
+    }
+
     pub fn kill(&mut self, spider_id: usize, animations: &mut Animations) {
         self.spider[spider_id].state = State::Dead;
         self.spiders_in_flight -= 1;
@@ -540,6 +1165,26 @@ impl<'a> Spiders<'a> {
         }
     }
 
+    // a diver that didn't pull up hits the ground
+    pub fn crashed(&self) -> Option<usize> {
+        self.spider.iter()
+            .position(|s| if let State::Dive(_) = s.state {s.y >= DIVER_GROUND_Y} else {false})
+    }
+
+    // any spider that has left the nest
+    pub fn flying(&self) -> Option<usize> {
+        self.spider.iter().position(|s| ! matches!(s.state, State::Nestle | State::Dead))
+    }
+
+    // the spiders still in the nest go down with a defeated mother
+    pub fn abandon_nest(&mut self) {
+        for s in self.spider.iter_mut().filter(|s| matches!(s.state, State::Nestle)) {
+            s.state = State::Dead;
+            self.spiders_left -= 1;
+        }
+        self.next_spider_launch = self.spider.len();
+    }
+
     pub fn spiders_remain(&self) -> bool {
         self.spiders_left > 0
     }
@@ -556,17 +1201,168 @@ impl<'a> Spiders<'a> {
         self.sound_on = false;
     }
 
+    #[cfg(feature = "debug-overlay")]
+    pub fn render_debug(&self, canvas: &mut Canvas<Window>, text: &Text) {
+        for spider in self.spider.iter() {
+            if let State::Nestle | State::Dead = spider.state {
+                continue;
+            }
+            let (name, (x_vel, y_vel), target) = spider.debug_state();
+            // swooping spider co-ords are for the centre of the spider
+            let (x, y) = if let State::Swoop(_, _) = spider.state
+                {(spider.x - SPIDER_WIDTH * 0.5, spider.y - SPIDER_HEIGHT * 0.5)}
+                else {(spider.x, spider.y)};
+            let centre_x = x + SPIDER_WIDTH * 0.5;
+            let centre_y = y + SPIDER_HEIGHT * 0.5;
+            if spider.collidable() {
+                self.hitbox(spider).render_debug(canvas, Color::RGB(255, 0, 0));
+            }
+            else {
+                debug_overlay::render_area(canvas, &spider.area(), Color::RGB(128, 128, 128));
+            }
+            debug_overlay::render_vector(canvas, centre_x, centre_y, x_vel, y_vel, Color::RGB(0, 255, 255));
+            if let Some(target_brick) = target {
+                debug_overlay::render_vector(canvas, centre_x, centre_y,
+                    (target_brick.x as f64 - centre_x) / 10.0,
+                    (target_brick.y as f64 - centre_y) / 10.0, Color::RGB(80, 80, 80));
+            }
+            text.render(canvas, x as i32, y as i32 - 14, 255, 255, 255, 255, name);
+        }
+    }
+
+    pub fn hash_state<H: Hasher>(&self, state: &mut H) {
+        for s in self.spider.iter().filter(|s| s.alive()) {
+            (s.x.to_bits(), s.y.to_bits(), s.next_bomb_release, s.hit_points).hash(state);
+            mem::discriminant(&s.state).hash(state);
+            match s.dodge {
+                Dodge::Reacting(n, dir) | Dodge::Sidestep(n, dir) => {(n, dir.to_bits()).hash(state)},
+                Dodge::Ignoring(n) => {n.hash(state)},
+                Dodge::Unaware => {},
+            }
+            mem::discriminant(&s.dodge).hash(state);
+        }
+        (self.spiders_left, self.spiders_in_flight, self.next_spider_launch,
+         self.last_launch_frame, self.next_wave_countdown, self.next_formation).hash(state);
+    }
+
+    // id:type:state:x:y for each spider still around, with the progress and
+    // direction of any turn and then hit points and flash after
+    pub fn describe(&self) -> String {
+        let spiders: Vec<String> = self.spider.iter().enumerate()
+            .filter(|(_, s)| s.alive())
+            .map(|(i, s)| {
+                let (code, n, r) = match s.state {
+                    State::Nestle => {("N", 0.0, 0.0)},
+                    State::Swoop(n, r) => {("S", n, r as f64)},
+                    State::Seek(_, _, _) => {("K", 0.0, 0.0)},
+                    State::Descend(_) => {("D", 0.0, 0.0)},
+                    State::Grab(n, r) => {("G", n, r)},
+                    State::Ascend => {("A", 0.0, 0.0)},
+                    State::Carry(_, _, _) => {("C", 0.0, 0.0)},
+                    State::Release(n, r) => {("R", n, r)},
+                    State::Formation(_, _, _) => {("F", 0.0, 0.0)},
+                    State::Lock(n) => {("L", n as f64, 0.0)},
+                    State::Dive(pull_up) => {("V", 0.0, if pull_up {1.0} else {0.0})},
+                    State::Climb => {("U", 0.0, 0.0)},
+                    State::Raid(_, _, _) => {("T", 0.0, 0.0)},
+                    State::Steal(n, r, _) => {("H", n, r)},
+                    State::Escape(_) => {("E", 0.0, 0.0)},
+                    State::Dead => {("X", 0.0, 0.0)},
+                };
+                format!("{}:{}:{}:{:.1}:{:.1}:{:.2}:{}:{}:{}", i, s.spider_type as usize, code, s.x, s.y, n, r,
+                    s.hit_points, s.flash_frames)
+            })
+            .collect();
+        spectator::list(&spiders)
+    }
+
+    pub fn watch(&mut self, description: &str) {
+        for s in self.spider.iter_mut() {
+            s.state = State::Dead;
+        }
+        let no_target = common::TargetBrick {x: 0, y: 0, brick_id: 0};
+        for d in spectator::items(description) {
+            let fields: Vec<&str> = d.split(':').collect();
+            let i: usize = spectator::field(&fields, 0, MAX_SPIDERS);
+            if i >= MAX_SPIDERS {
+                continue;
+            }
+            if i >= self.spider.len() {
+                self.spider.resize(i + 1, Spider::new());
+            }
+            let s = &mut self.spider[i];
+            s.spider_type = match spectator::field(&fields, 1, 1) {
+                0 => {Type::Slow},
+                2 => {Type::Fast},
+                3 => {Type::Diver},
+                4 => {Type::Armoured},
+                5 => {Type::Thief},
+                _ => {Type::Medium},
+            };
+            s.x = spectator::field(&fields, 3, 0.0);
+            s.y = spectator::field(&fields, 4, 0.0);
+            let n = spectator::field(&fields, 5, 0.0);
+            let r = spectator::field(&fields, 6, 0.0);
+            s.hit_points = spectator::field(&fields, 7, 1);
+            s.flash_frames = spectator::field(&fields, 8, 0);
+            s.state = match fields.get(2) {
+                Some(&"N") => {State::Nestle},
+                Some(&"S") => {State::Swoop(n, r as i32)},
+                Some(&"K") => {State::Seek(0.0, 0.0, None)},
+                Some(&"D") => {State::Descend(no_target)},
+                Some(&"G") => {State::Grab(n, r)},
+                Some(&"A") => {State::Ascend},
+                Some(&"C") => {State::Carry(0.0, 0.0, None)},
+                Some(&"R") => {State::Release(n, r)},
+                Some(&"F") => {State::Formation(0, 0, 0)},
+                Some(&"L") => {State::Lock(n as u32)},
+                Some(&"V") => {State::Dive(r > 0.0)},
+                Some(&"U") => {State::Climb},
+                Some(&"T") => {State::Raid(0.0, 0.0, None)},
+                Some(&"H") => {State::Steal(n, r, 0)},
+                Some(&"E") => {State::Escape(0)},
+                _ => {State::Dead},
+            };
+        }
+    }
+
+    // a dashed line down to where a diver is about to dive
+    fn render_dive_line(canvas: &mut Canvas<Window>, spider: &Spider) {
+        let x = (spider.x + SPIDER_WIDTH / 2.0) as i32;
+        canvas.set_draw_color(Color::RGBA(255, 80, 40, 160));
+        for y in ((spider.y + SPIDER_HEIGHT) as i32..DIVER_GROUND_Y as i32).step_by(12) {
+            let _ = canvas.draw_line(Point::new(x, y), Point::new(x, y + 6));
+        }
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+    }
+
+    // armoured spiders change once damaged and flash as they are hit
+    fn images(&self, spider: &Spider, laden: bool) -> &[Image<'a>; 4] {
+        let l = laden as usize;
+        if spider.flash_frames > 0 && (spider.flash_frames / 3).is_multiple_of(2) {
+            &self.spider_image_flash[l]
+        }
+        else if spider.damaged() {
+            &self.spider_image_damaged[l]
+        }
+        else if laden {
+            &self.spider_image_laden[spider.spider_type as usize]
+        }
+        else {
+            &self.spider_image_empty[spider.spider_type as usize]
+        }
+    }
+
     pub fn render(&self, mother: &Mother, canvas: &mut Canvas<Window>, frame_count: u32) {
         let (mother_x, mother_y) = mother.location();
         for spider in self.spider.iter() {
             let anim_frame = (((frame_count + spider.anim_offset) % SPIDER_PERIOD) /
                 (SPIDER_PERIOD / 4)) as usize;
-            let type_i = spider.spider_type as usize;
             match spider.state {
                 State::Nestle => {
                     let x = spider.x as i32 + mother_x;
                     let y = spider.y as i32 + mother_y - 8;
-                    let _ = &self.spider_image_empty[type_i][3]
+                    let _ = &self.images(spider, false)[3]
                         .render_angle(canvas, x, y, 180.0, 0.2);
                 },
                 State::Swoop(n, r) => {
@@ -574,35 +1370,56 @@ impl<'a> Spiders<'a> {
                     let angle = 180.0 + 180.0 * n * r as f64;
                     let x = (spider.x - SPIDER_WIDTH * 0.5 * scale) as i32;
                     let y = (spider.y - SPIDER_HEIGHT * 0.5 * scale) as i32;
-                    let _ = &self.spider_image_empty[type_i][anim_frame]
+                    let _ = &self.images(spider, false)[anim_frame]
                         .render_angle(canvas, x, y, angle, scale);
                 },
                 State::Seek(_, _, _) => {
-                    let _ = &self.spider_image_empty[type_i][anim_frame]
+                    let _ = &self.images(spider, false)[anim_frame]
                         .render(canvas, spider.x as i32, spider.y as i32);
                 },
                 State::Descend(_) => {
-                    let _ = &self.spider_image_empty[type_i][anim_frame]
+                    let _ = &self.images(spider, false)[anim_frame]
                         .render(canvas, spider.x as i32, spider.y as i32);
                 },
                 State::Grab(n, r) => {
                     let angle = 180.0 + 180.0 * n * r;
-                    let _ = &self.spider_image_laden[type_i][anim_frame]
+                    let _ = &self.images(spider, true)[anim_frame]
                         .render_angle(canvas, spider.x as i32, spider.y as i32, angle, 1.0);
                 },
                 State::Ascend => {
-                    let _ = &self.spider_image_laden[type_i][anim_frame]
+                    let _ = &self.images(spider, true)[anim_frame]
                         .render(canvas, spider.x as i32, spider.y as i32);
                 },
                 State::Carry(_, _, _) => {
-                    let _ = &self.spider_image_laden[type_i][anim_frame]
+                    let _ = &self.images(spider, true)[anim_frame]
                         .render(canvas, spider.x as i32, spider.y as i32);
                 },
                 State::Release(n, r) => {
                     let angle = 180.0 + 180.0 * n * r;
-                    let _ = &self.spider_image_empty[type_i][anim_frame]
+                    let _ = &self.images(spider, false)[anim_frame]
+                        .render_angle(canvas, spider.x as i32, spider.y as i32, angle, 1.0);
+                },
+                State::Lock(n) => {
+                    let angle = spider.telegraph_angle();
+                    let _ = &self.images(spider, false)[anim_frame]
+                        .render_angle(canvas, spider.x as i32, spider.y as i32, angle, 1.0);
+                    if n >= DIVER_LOCK_FRAMES - DIVER_TELEGRAPH_FRAMES {
+                        Spiders::render_dive_line(canvas, spider);
+                    }
+                },
+                State::Dive(_) | State::Climb | State::Raid(_, _, _) | State::Formation(_, _, _) => {
+                    let _ = &self.images(spider, false)[anim_frame]
+                        .render(canvas, spider.x as i32, spider.y as i32);
+                },
+                State::Steal(n, r, _) => {
+                    let angle = 180.0 + 180.0 * n * r;
+                    let _ = &self.images(spider, true)[anim_frame]
                         .render_angle(canvas, spider.x as i32, spider.y as i32, angle, 1.0);
                 },
+                State::Escape(_) => {
+                    let _ = &self.images(spider, true)[anim_frame]
+                        .render(canvas, spider.x as i32, spider.y as i32);
+                },
                 _ => {},
             };
         }
@@ -0,0 +1,176 @@
+// steering behaviours for flying spiders - each frame a spider works out the
+// velocity it would like from seeking or arriving at a target, wandering,
+// keeping clear of the others and staying in bounds, then turns towards it
+// only as fast as its type allows
+
+use std::f64::consts::PI;
+
+use crate::common;
+
+// slow down when this close to the target, but never below the minimum
+// speed so that the target is always reached
+const ARRIVE_RADIUS: f64 = 60.0;
+const ARRIVE_MIN_SPEED: f64 = 1.0;
+// how far the heading drifts each frame when wandering
+const WANDER_JITTER: f64 = 0.15;
+const SEPARATION_RADIUS: f64 = 36.0;
+const SEPARATION_WEIGHT: f64 = 1.5;
+// start turning back this far from the edge of the bounds
+const BOUNDS_MARGIN: f64 = 40.0;
+
+pub struct Steering {
+    pub max_speed: f64,
+    // radians per frame
+    pub turn_rate: f64,
+    // change in speed per frame
+    pub acceleration: f64,
+}
+
+pub struct Bounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl Steering {
+    // turn the velocity towards the desired one, limited by the turn rate
+    // and acceleration
+    pub fn steer(&self, vel: (f64, f64), desired: (f64, f64)) -> (f64, f64) {
+        let (x_vel, y_vel) = vel;
+        let (x_desired, y_desired) = desired;
+        let speed = x_vel.hypot(y_vel);
+        let desired_speed = x_desired.hypot(y_desired).min(self.max_speed);
+        let desired_heading = y_desired.atan2(x_desired);
+        let heading = if speed == 0.0 {
+            desired_heading
+        }
+        else {
+            let heading = y_vel.atan2(x_vel);
+            heading + normalise_angle(desired_heading - heading).clamp(-self.turn_rate, self.turn_rate)
+        };
+        let speed = speed + (desired_speed - speed).clamp(-self.acceleration, self.acceleration);
+        (heading.cos() * speed, heading.sin() * speed)
+    }
+}
+
+fn normalise_angle(angle: f64) -> f64 {
+    let angle = angle % (2.0 * PI);
+    if angle > PI {angle - 2.0 * PI} else if angle < -PI {angle + 2.0 * PI} else {angle}
+}
+
+pub fn seek(x: f64, y: f64, target_x: f64, target_y: f64, max_speed: f64) -> (f64, f64) {
+    let distance = (target_x - x).hypot(target_y - y);
+    if distance == 0.0 {
+        return (0.0, 0.0);
+    }
+    ((target_x - x) / distance * max_speed, (target_y - y) / distance * max_speed)
+}
+
+pub fn arrive(x: f64, y: f64, target_x: f64, target_y: f64, max_speed: f64) -> (f64, f64) {
+    let distance = (target_x - x).hypot(target_y - y);
+    let speed = (max_speed * distance / ARRIVE_RADIUS).clamp(ARRIVE_MIN_SPEED, max_speed);
+    seek(x, y, target_x, target_y, speed)
+}
+
+pub fn wander(vel: (f64, f64), max_speed: f64) -> (f64, f64) {
+    let (x_vel, y_vel) = vel;
+    let heading = y_vel.atan2(x_vel) + common::random_range(-WANDER_JITTER..WANDER_JITTER);
+    (heading.cos() * max_speed, heading.sin() * max_speed)
+}
+
+// a push away from any others that are too close, stronger the closer
+// they are
+pub fn separation(x: f64, y: f64, others: &[(f64, f64)]) -> (f64, f64) {
+    let mut push = (0.0, 0.0);
+    for &(other_x, other_y) in others {
+        let distance = (x - other_x).hypot(y - other_y);
+        if distance > 0.0 && distance < SEPARATION_RADIUS {
+            let strength = SEPARATION_WEIGHT * (1.0 - distance / SEPARATION_RADIUS);
+            push.0 += (x - other_x) / distance * strength;
+            push.1 += (y - other_y) / distance * strength;
+        }
+    }
+    push
+}
+
+// near the edge of the bounds the desired velocity is turned back in
+pub fn contain(x: f64, y: f64, desired: (f64, f64), bounds: &Bounds, max_speed: f64) -> (f64, f64) {
+    let (mut x_desired, mut y_desired) = desired;
+    if x < bounds.x_min + BOUNDS_MARGIN {
+        x_desired = x_desired.max(max_speed * (bounds.x_min + BOUNDS_MARGIN - x) / BOUNDS_MARGIN);
+    }
+    else if x > bounds.x_max - BOUNDS_MARGIN {
+        x_desired = x_desired.min(- max_speed * (x - bounds.x_max + BOUNDS_MARGIN) / BOUNDS_MARGIN);
+    }
+    if y < bounds.y_min + BOUNDS_MARGIN {
+        y_desired = y_desired.max(max_speed * (bounds.y_min + BOUNDS_MARGIN - y) / BOUNDS_MARGIN);
+    }
+    else if y > bounds.y_max - BOUNDS_MARGIN {
+        y_desired = y_desired.min(- max_speed * (y - bounds.y_max + BOUNDS_MARGIN) / BOUNDS_MARGIN);
+    }
+    (x_desired, y_desired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Bounds = Bounds {x_min: 0.0, x_max: 400.0, y_min: 200.0, y_max: 480.0};
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn seek_heads_straight_for_the_target() {
+        let (x_vel, y_vel) = seek(0.0, 0.0, 30.0, 40.0, 5.0);
+        assert!(close(x_vel, 3.0));
+        assert!(close(y_vel, 4.0));
+        assert_eq!(seek(10.0, 10.0, 10.0, 10.0, 5.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn arrive_slows_down_but_keeps_moving() {
+        let (x_vel, _) = arrive(0.0, 0.0, ARRIVE_RADIUS * 2.0, 0.0, 4.0);
+        assert!(close(x_vel, 4.0));
+        let (x_vel, _) = arrive(0.0, 0.0, ARRIVE_RADIUS / 2.0, 0.0, 4.0);
+        assert!(close(x_vel, 2.0));
+        let (x_vel, _) = arrive(0.0, 0.0, 0.1, 0.0, 4.0);
+        assert!(close(x_vel, ARRIVE_MIN_SPEED));
+    }
+
+    #[test]
+    fn contain_turns_back_near_the_edges() {
+        // heading out of the left and bottom edges
+        let (x_desired, y_desired) = contain(10.0, 470.0, (-3.0, 3.0), &BOUNDS, 3.0);
+        assert!(x_desired > 0.0);
+        assert!(y_desired < 0.0);
+        // well inside nothing changes
+        assert_eq!(contain(200.0, 340.0, (-3.0, 3.0), &BOUNDS, 3.0), (-3.0, 3.0));
+    }
+
+    #[test]
+    fn steer_turns_no_faster_than_the_turn_rate() {
+        let steering = Steering {max_speed: 3.0, turn_rate: 0.1, acceleration: 0.5};
+        // flying right and wanting to go straight back
+        let (x_vel, y_vel) = steering.steer((3.0, 0.0), (-3.0, 0.0));
+        assert!(close(y_vel.atan2(x_vel).abs(), 0.1));
+        assert!(close(x_vel.hypot(y_vel), 3.0));
+        // a small turn is made in one go
+        let (x_vel, y_vel) = steering.steer((3.0, 0.0), (3.0, 0.15));
+        assert!(close(y_vel.atan2(x_vel), 0.05f64.atan()));
+    }
+
+    #[test]
+    fn steer_speeds_up_no_faster_than_the_acceleration() {
+        let steering = Steering {max_speed: 3.0, turn_rate: 0.1, acceleration: 0.5};
+        let (x_vel, y_vel) = steering.steer((1.0, 0.0), (10.0, 0.0));
+        assert!(close(x_vel, 1.5));
+        assert!(close(y_vel, 0.0));
+        // from standing it sets off the way it wants to go
+        let (x_vel, y_vel) = steering.steer((0.0, 0.0), (0.0, -3.0));
+        assert!(close(x_vel.abs(), 0.0));
+        assert!(close(y_vel, -0.5));
+    }
+}
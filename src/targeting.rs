@@ -0,0 +1,78 @@
+// how spiders choose which brick to go for, in the base piles or the gaps
+// in the word. The bricks make a list of the ones a spider may go for and a
+// strategy picks one of them. Which strategies an attack uses is set in
+// attacks.rs
+
+use crate::common;
+
+pub struct Candidate {
+    pub id: usize,
+    pub x: i32,
+    pub y: i32,
+    // the letter or pile the brick is in and how near that is to being
+    // finished, 0.0 to 1.0, for the word that is filled and for the piles
+    // taken away
+    pub group: usize,
+    pub progress: f64,
+}
+
+pub trait Targeting {
+    // an index into the candidates, of which there is at least one, for a
+    // spider with its top left at x, y
+    fn choose(&self, candidates: &[Candidate], x: f64, y: f64) -> usize;
+}
+
+// any of them, as spiders have always done
+pub struct Random;
+
+// the letter or pile closest to being finished
+pub struct Focus;
+
+// the letter or pile furthest from being finished, so they all come along
+// together
+pub struct Spread;
+
+// whichever brick is closest to the spider
+pub struct Nearest;
+
+impl Targeting for Random {
+    fn choose(&self, candidates: &[Candidate], _x: f64, _y: f64) -> usize {
+        common::random_range(0..candidates.len())
+    }
+}
+
+impl Targeting for Focus {
+    fn choose(&self, candidates: &[Candidate], _x: f64, _y: f64) -> usize {
+        let best = candidates.iter().map(|c| c.progress).fold(f64::MIN, f64::max);
+        random_in_group(candidates, best)
+    }
+}
+
+impl Targeting for Spread {
+    fn choose(&self, candidates: &[Candidate], _x: f64, _y: f64) -> usize {
+        let best = candidates.iter().map(|c| c.progress).fold(f64::MAX, f64::min);
+        random_in_group(candidates, best)
+    }
+}
+
+impl Targeting for Nearest {
+    fn choose(&self, candidates: &[Candidate], x: f64, y: f64) -> usize {
+        let distance = |c: &Candidate| (c.x as f64 - x).hypot(c.y as f64 - y);
+        let mut nearest = 0;
+        for (i, c) in candidates.iter().enumerate() {
+            if distance(c) < distance(&candidates[nearest]) {
+                nearest = i;
+            }
+        }
+        nearest
+    }
+}
+
+// any of the candidates in the first group to have the given progress
+fn random_in_group(candidates: &[Candidate], progress: f64) -> usize {
+    let group = candidates.iter().find(|c| c.progress == progress).map_or(0, |c| c.group);
+    let in_group: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].group == group)
+        .collect();
+    in_group[common::random_range(0..in_group.len())]
+}